@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS guild_settings(
+    id_guild_settings SERIAL,
+    guild_id BIGINT NOT NULL,
+    leap_day_policy INTEGER NOT NULL DEFAULT 0,
+    create_date TIMESTAMP NOT NULL,
+    modify_date TIMESTAMP,
+    PRIMARY KEY (id_guild_settings),
+    UNIQUE (guild_id)
+);
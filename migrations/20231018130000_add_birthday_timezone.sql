@@ -0,0 +1 @@
+ALTER TABLE birthday ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'UTC';
@@ -0,0 +1 @@
+ALTER TABLE subscription ADD COLUMN IF NOT EXISTS notify_time TIME;
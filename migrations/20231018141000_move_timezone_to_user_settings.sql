@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS user_settings(
+    id_user_settings SERIAL,
+    user_id BIGINT NOT NULL,
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    create_date TIMESTAMP NOT NULL,
+    modify_date TIMESTAMP,
+    PRIMARY KEY (id_user_settings),
+    UNIQUE (user_id)
+);
+
+-- The timezone of the most recently changed birthday of every user.
+INSERT INTO user_settings (user_id, timezone, create_date)
+    SELECT DISTINCT ON (user_id) user_id, timezone, NOW()
+    FROM birthday
+    ORDER BY user_id, COALESCE(modify_date, create_date) DESC;
+
+ALTER TABLE birthday DROP COLUMN IF EXISTS timezone;
@@ -0,0 +1,6 @@
+ALTER TABLE subscription ADD COLUMN IF NOT EXISTS reminder_days INTEGER[] NOT NULL DEFAULT '{0}';
+
+ALTER TABLE send_notifications ADD COLUMN IF NOT EXISTS days_before INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE send_notifications DROP CONSTRAINT IF EXISTS send_notifications_subscription_id_current_year_key;
+ALTER TABLE send_notifications ADD CONSTRAINT send_notifications_subscription_id_current_year_days_before_key
+    UNIQUE (subscription_id, current_year, days_before);
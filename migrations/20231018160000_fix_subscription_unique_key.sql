@@ -0,0 +1,3 @@
+ALTER TABLE subscription DROP CONSTRAINT IF EXISTS subscription_user_id_guild_id_key;
+ALTER TABLE subscription ADD CONSTRAINT subscription_user_id_guild_id_birthday_id_key
+    UNIQUE (user_id, guild_id, birthday_id);
@@ -0,0 +1,11 @@
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS announcement_channel_id BIGINT;
+
+CREATE TABLE IF NOT EXISTS send_announcements(
+    id_send_announcement SERIAL,
+    birthday_id INTEGER NOT NULL,
+    current_year INTEGER NOT NULL,
+    create_date TIMESTAMP NOT NULL,
+    PRIMARY KEY (id_send_announcement),
+    UNIQUE (birthday_id, current_year),
+    FOREIGN KEY (birthday_id) REFERENCES birthday(id_birthday) ON DELETE CASCADE
+);
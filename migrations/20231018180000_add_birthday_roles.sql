@@ -0,0 +1,15 @@
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS birthday_role_id BIGINT;
+
+CREATE TABLE IF NOT EXISTS role_assignments(
+    id_role_assignment SERIAL,
+    birthday_id INTEGER NOT NULL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    role_id BIGINT NOT NULL,
+    current_year INTEGER NOT NULL,
+    remove_date TIMESTAMP NOT NULL,
+    removed_date TIMESTAMP,
+    create_date TIMESTAMP NOT NULL,
+    PRIMARY KEY (id_role_assignment),
+    UNIQUE (birthday_id, current_year)
+);
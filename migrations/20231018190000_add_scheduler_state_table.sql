@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS scheduler_state(
+    id_scheduler_state INTEGER NOT NULL,
+    last_run_date TIMESTAMP NOT NULL,
+    PRIMARY KEY (id_scheduler_state)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE birthday ADD COLUMN IF NOT EXISTS hide_year BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE birthday ADD COLUMN IF NOT EXISTS visibility INTEGER NOT NULL DEFAULT 0;
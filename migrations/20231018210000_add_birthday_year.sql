@@ -0,0 +1,3 @@
+-- The year of `date` is only meaningful as long as `year` is set.
+ALTER TABLE birthday ADD COLUMN IF NOT EXISTS year INTEGER;
+UPDATE birthday SET year = EXTRACT(YEAR FROM date)::INTEGER WHERE year IS NULL;
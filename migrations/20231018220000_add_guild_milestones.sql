@@ -0,0 +1 @@
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS milestones INTEGER[] NOT NULL DEFAULT '{18,21,30,40,50,60,70,80,90,100}';
@@ -0,0 +1,2 @@
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS dm_template TEXT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS announcement_template TEXT;
@@ -0,0 +1,3 @@
+ALTER TABLE birthday ADD COLUMN IF NOT EXISTS modified_by BIGINT;
+
+UPDATE birthday SET modified_by = user_id WHERE modified_by IS NULL;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS audit_log(
+    id_audit_log SERIAL,
+    guild_id BIGINT NOT NULL,
+    actor_id BIGINT NOT NULL,
+    target_id BIGINT,
+    action INTEGER NOT NULL,
+    before_data JSONB,
+    after_data JSONB,
+    create_date TIMESTAMP NOT NULL,
+    PRIMARY KEY (id_audit_log)
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_guild_target_idx ON audit_log (guild_id, target_id);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS pending_import(
+    id_pending_import SERIAL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    import_rows JSONB NOT NULL,
+    create_date TIMESTAMP NOT NULL,
+    PRIMARY KEY (id_pending_import)
+);
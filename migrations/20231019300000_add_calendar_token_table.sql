@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS calendar_token(
+    id_calendar_token SERIAL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    token TEXT NOT NULL,
+    create_date TIMESTAMP NOT NULL,
+    PRIMARY KEY (id_calendar_token),
+    UNIQUE (guild_id, user_id),
+    UNIQUE (token)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE send_announcements ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE send_announcements ADD COLUMN IF NOT EXISTS retry_date TIMESTAMP;
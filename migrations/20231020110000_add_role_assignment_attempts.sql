@@ -0,0 +1,2 @@
+ALTER TABLE role_assignments ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE role_assignments ADD COLUMN IF NOT EXISTS retry_date TIMESTAMP;
@@ -0,0 +1,7 @@
+-- The birth year is only stored in `year`, the date always falls into the year 2000.
+UPDATE birthday
+SET date = MAKE_TIMESTAMP(2000, EXTRACT(MONTH FROM date)::INTEGER, EXTRACT(DAY FROM date)::INTEGER, 0, 0, 0)
+WHERE EXTRACT(YEAR FROM date) <> 2000;
+ALTER TABLE birthday ADD CONSTRAINT birthday_date_placeholder_year CHECK (EXTRACT(YEAR FROM date) = 2000);
+-- Pending imports expire within minutes and may still hold dates with a birth year.
+DELETE FROM pending_import;
@@ -0,0 +1,3 @@
+-- Only a hash of the token is stored, a leaked database does not expose the calendar feeds.
+ALTER TABLE calendar_token RENAME COLUMN token TO token_hash;
+UPDATE calendar_token SET token_hash = ENCODE(SHA256(CONVERT_TO(token_hash, 'UTF8')), 'hex');
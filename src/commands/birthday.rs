@@ -1,52 +1,104 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 
-use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use chrono::{Datelike, Months, Weekday};
+use chrono_tz::{Tz, TZ_VARIANTS};
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
 use serenity::futures::future::join_all;
 use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::component::ButtonStyle;
 use serenity::model::prelude::interaction::application_command::CommandDataOption;
-use serenity::model::prelude::GuildId;
+use serenity::model::prelude::{AttachmentType, GuildId};
 use serenity::model::user::User;
 use serenity::prelude::Context;
-use sqlx::types::chrono::Utc;
+use sqlx::types::chrono::{DateTime, NaiveDate, Utc};
 use sqlx::PgPool;
 
-use crate::models::birthday::Birthday;
-use crate::models::subscription::Subscription;
+use crate::i18n::{self, Language, Localize};
+use crate::models::birthday::{Birthday, Visibility};
+use crate::models::calendar_token::CalendarToken;
+use crate::models::guild_settings::GuildSettings;
+use crate::models::subscription::{Subscription, MAX_REMINDER_DAYS};
+use crate::models::user_data::{UserDataErasure, UserDataExport};
+use crate::models::user_settings::UserSettings;
+use crate::recurrence::{self, LeapDayPolicy};
+use crate::server::CALENDAR_PATH;
 use crate::utils;
 
-use super::parser::{DateInputParser, UserInputParser};
-use super::CommandError;
+use super::parser::{
+    DateInputParser, OptionalBooleanInputParser, OptionalIntegerInputParser,
+    OptionalReminderInputParser, OptionalTimeInputParser, OptionalUserInputParser, ParserError,
+    TimezoneInputParser, UserInputParser,
+};
+use super::{CommandError, CommandResponse};
+
+/// The maximum number of fields an embed can contain.
+const SUBSCRIPTIONS_PER_PAGE: usize = 25;
+/// The number of upcoming birthdays shown at once, one field each.
+const UPCOMING_PER_PAGE: usize = 25;
+/// How many days `/birthday upcoming` looks ahead if no days are given.
+const DEFAULT_UPCOMING_DAYS: i64 = 30;
+/// The maximum length of the description of an embed.
+const MAX_EMBED_DESCRIPTION_LENGTH: usize = 4096;
 
 pub async fn run_info_command(
     db: &PgPool,
     ctx: &Context,
     guild_id: &GuildId,
     user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let target = OptionalUserInputParser
+        .parse(options, "user")
+        .map_err(CommandError::Parser)?;
+
+    match target {
+        Some(target) if target.id != user.id => {
+            run_member_info_command(db, guild_id, user, &target, language).await
+        }
+        _ => run_own_info_command(db, ctx, guild_id, user, language)
+            .await
+            .map(CommandResponse::from),
+    }
+}
+
+async fn run_own_info_command(
+    db: &PgPool,
+    ctx: &Context,
+    guild_id: &GuildId,
+    user: &User,
+    language: Language,
 ) -> Result<CreateEmbed, CommandError> {
     if let Some(bday) = Birthday::get(db, guild_id.0, user.id.0)
         .await
-        .map_err(|x| CommandError::Db(x))?
+        .map_err(CommandError::Db)?
     {
         let subscriptions = Subscription::get_all_by_guild_and_user(db, guild_id.0, user.id.0)
             .await
-            .map_err(|x| CommandError::Db(x))?;
+            .map_err(CommandError::Db)?;
 
         let fields = subscriptions
             .iter()
-            .map(|s| async { gen_embed_field(db, guild_id.0, &ctx, s).await });
+            .map(|s| async { gen_embed_field(db, guild_id.0, ctx, s, language).await });
 
         let fields: Result<Vec<(String, String, bool)>, CommandError> =
             join_all(fields).await.into_iter().collect();
 
         let embed = CreateEmbed(HashMap::new())
-            .title("Birthday:")
-            .description(format!("{}", bday.date.date()))
+            .title(language.tr("title.birthday"))
+            .description(bday.format_date(true))
             .author(|author| {
                 author
                     .name(user.name.clone())
                     .icon_url(utils::get_icon_url(user))
             })
-            .field("Subscriptions:", "", false)
+            .field(
+                language.tr("info.field.privacy"),
+                gen_privacy_text(&bday, language),
+                false,
+            )
+            .field(language.tr("info.field.subscriptions"), "", false)
             .fields(fields?)
             .to_owned();
 
@@ -54,8 +106,8 @@ pub async fn run_info_command(
     }
 
     let embed = CreateEmbed(HashMap::new())
-        .title("Birthday:")
-        .description("You have not registered your birthday yet.")
+        .title(language.tr("title.birthday"))
+        .description(language.tr("info.not-registered"))
         .author(|author| {
             author
                 .name(user.name.clone())
@@ -66,42 +118,197 @@ pub async fn run_info_command(
     Ok(embed)
 }
 
+async fn run_member_info_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    target: &User,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let birthday = match Birthday::get(db, guild_id.0, target.id.0)
+        .await
+        .map_err(CommandError::Db)?
+    {
+        Some(birthday) => birthday,
+        None => {
+            let embed = CreateEmbed(HashMap::new())
+                .title(language.tr("title.birthday"))
+                .description(language.tr_args(
+                    "info.member-not-registered",
+                    &[("user", format!("<@{}>", target.id))],
+                ))
+                .author(|author| {
+                    author
+                        .name(target.name.clone())
+                        .icon_url(utils::get_icon_url(target))
+                })
+                .to_owned();
+
+            return Ok(embed.into());
+        }
+    };
+
+    let now = Utc::now();
+    let settings = GuildSettings::get_or_default(db, guild_id.0, now.naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+    let timezone = UserSettings::get_timezone(db, target.id.0)
+        .await
+        .map_err(CommandError::Db)?;
+    let today = now.with_timezone(&timezone).date_naive();
+    let next =
+        recurrence::next_occurrence(birthday.date.date(), today, settings.leap_day_policy());
+
+    let is_subscribed = Subscription::get(db, guild_id.0, user.id.0, birthday.id_birthday)
+        .await
+        .map_err(CommandError::Db)?
+        .is_some();
+
+    let description = if birthday.is_visible_to(user.id.0, is_subscribed) {
+        format!(
+            "{} ({})",
+            birthday.display_date(),
+            gen_days_remaining_text((next - today).num_days(), language)
+        )
+    } else {
+        language.tr_args("info.member-private", &[("user", format!("<@{}>", target.id))])
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.birthday"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(target.name.clone())
+                .icon_url(utils::get_icon_url(target))
+        })
+        .to_owned();
+
+    let mut components = CreateComponents::default();
+
+    // Only existing subscribers see the button of a birthday visible to subscribers only.
+    let has_button = match birthday.visibility() {
+        Visibility::Everyone => true,
+        Visibility::SubscribersOnly => is_subscribed,
+        Visibility::AnnouncementsOnly => false,
+    };
+
+    if !has_button {
+        return Ok(CommandResponse { embed, components });
+    }
+
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id(format!("subscribe:{}:{}", user.id, target.id))
+                .label(match is_subscribed {
+                    true => language.tr("info.button.subscribed"),
+                    false => language.tr("info.button.subscribe"),
+                })
+                .style(ButtonStyle::Primary)
+                .disabled(is_subscribed)
+        })
+    });
+
+    Ok(CommandResponse { embed, components })
+}
+
+/// Subscribes to a birthday with the default settings, as done by the button of `/birthday info <user>`.
+pub async fn run_subscribe_button(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    target_id: u64,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let description = match Birthday::get(db, guild_id.0, target_id)
+        .await
+        .map_err(CommandError::Db)?
+        .filter(|b| b.visibility() != Visibility::AnnouncementsOnly)
+    {
+        Some(birthday) => {
+            match Subscription::get(db, guild_id.0, user.id.0, birthday.id_birthday)
+                .await
+                .map_err(CommandError::Db)?
+            {
+                Some(_) => language.tr("subscribe.already-subscribed"),
+                None if !birthday.accepts_subscriptions() => language.tr_args(
+                    "subscribe.closed",
+                    &[("user", format!("<@{}>", target_id))],
+                ),
+                None => {
+                    let mut subscription = Subscription::new(
+                        guild_id.0,
+                        user.id.0,
+                        birthday.id_birthday,
+                        None,
+                        vec![0],
+                        Utc::now().naive_utc(),
+                    );
+                    subscription
+                        .insert(db, user.id.0)
+                        .await
+                        .map_err(CommandError::from_db)?;
+
+                    language.tr_args(
+                        "subscribe.subscribed",
+                        &[("user", format!("<@{}>", target_id))],
+                    )
+                }
+            }
+        }
+        None => language.tr("subscribe.no-birthday"),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.subscription"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed.into())
+}
+
 pub async fn run_set_command(
     db: &PgPool,
     guild_id: &GuildId,
     user: &User,
     options: &[CommandDataOption],
+    language: Language,
 ) -> Result<CreateEmbed, CommandError> {
     let date_parser = DateInputParser;
-    let date = date_parser
+    let (date, year) = date_parser
         .parse(options)
-        .map_err(|x| CommandError::Parser(x))?;
+        .map_err(CommandError::Parser)?;
 
-    let mut text_part = "set";
+    let mut text_key = "set.set";
     let mut birthday: Birthday;
 
     if let Some(mut bday) = Birthday::get(db, guild_id.0, user.id.0)
         .await
-        .map_err(|x| CommandError::Db(x))?
+        .map_err(CommandError::Db)?
     {
         bday.date = date;
+        bday.year = year;
         bday.modify_date = Some(Utc::now().naive_utc());
-        bday.update(db).await.map_err(|x| CommandError::Db(x))?;
+        bday.set_modified_by(user.id.0);
+        bday.update(db, user.id.0).await.map_err(CommandError::Db)?;
 
-        text_part = "updated";
+        text_key = "set.updated";
         birthday = bday;
     } else {
-        birthday = Birthday::new(guild_id.0, user.id.0, date, Utc::now().naive_utc());
-        birthday.insert(db).await.map_err(|x| CommandError::Db(x))?;
+        birthday = Birthday::new(guild_id.0, user.id.0, date, year, Utc::now().naive_utc());
+        birthday.set_modified_by(user.id.0);
+        birthday.insert(db, user.id.0).await.map_err(CommandError::from_db)?;
     }
 
     let embed = CreateEmbed(HashMap::new())
-        .title("Birthday:")
-        .description(format!(
-            "Birthday has been {} to: {}",
-            text_part,
-            birthday.date.date()
-        ))
+        .title(language.tr("title.birthday"))
+        .description(language.tr_args(text_key, &[("date", birthday.format_date(true))]))
         .author(|author| {
             author
                 .name(user.name.clone())
@@ -116,16 +323,17 @@ pub async fn run_remove_command(
     db: &PgPool,
     guild_id: &GuildId,
     user: &User,
+    language: Language,
 ) -> Result<CreateEmbed, CommandError> {
     if let Some(birthday) = Birthday::get(db, guild_id.0, user.id.0)
         .await
-        .map_err(|x| CommandError::Db(x))?
+        .map_err(CommandError::Db)?
     {
-        birthday.delete(db).await.map_err(|x| CommandError::Db(x))?;
+        birthday.delete(db, user.id.0).await.map_err(CommandError::Db)?;
 
         let embed = CreateEmbed(HashMap::new())
-            .title("Birthday:")
-            .description("Your birthday and all subscriptions to this birthday has been deleted.")
+            .title(language.tr("title.birthday"))
+            .description(language.tr("remove.removed"))
             .author(|author| {
                 author
                     .name(user.name.clone())
@@ -137,8 +345,8 @@ pub async fn run_remove_command(
     }
 
     let embed = CreateEmbed(HashMap::new())
-        .title("Birthday:")
-        .description("You currently have no birthday set up, which i could delete!")
+        .title(language.tr("title.birthday"))
+        .description(language.tr("remove.not-registered"))
         .author(|author| {
             author
                 .name(user.name.clone())
@@ -154,35 +362,37 @@ pub async fn run_subscribe_command(
     guild_id: &GuildId,
     user: &User,
     options: &[CommandDataOption],
+    language: Language,
 ) -> Result<CreateEmbed, CommandError> {
     let user_to_subcribe_to = UserInputParser
         .parse(options, 0)
-        .map_err(|x| CommandError::Parser(x))?;
+        .map_err(CommandError::Parser)?;
 
-    if let Some(birthday) = Birthday::get(db, guild_id.0, user_to_subcribe_to.id.0)
-        .await
-        .map_err(|x| CommandError::Db(x))?
-    {
-        if let None = Subscription::get(db, guild_id.0, user.id.0, birthday.id_birthday)
-            .await
-            .map_err(|x| CommandError::Db(x))?
-        {
-            let mut subscription = Subscription::new(
-                guild_id.0,
-                user.id.0,
-                birthday.id_birthday,
-                Utc::now().naive_utc(),
-            );
-            subscription
-                .insert(db)
-                .await
-                .map_err(|x| CommandError::Db(x))?;
+    let notify_time = match OptionalTimeInputParser.parse(options, "time") {
+        Ok(notify_time) => notify_time,
+        Err(_) => {
+            let embed = CreateEmbed(HashMap::new())
+                .title(language.tr("title.subscription"))
+                .description(language.tr("subscribe.invalid-time"))
+                .author(|author| {
+                    author
+                        .name(user.name.clone())
+                        .icon_url(utils::get_icon_url(user))
+                })
+                .to_owned();
+
+            return Ok(embed);
+        }
+    };
 
+    let reminder_days = match OptionalReminderInputParser.parse(options, "reminders") {
+        Ok(reminder_days) => reminder_days,
+        Err(_) => {
             let embed = CreateEmbed(HashMap::new())
-                .title("Birthday Subscription:")
-                .description(format!(
-                    "You are now subcribed to the birthday of <@{}>.",
-                    user_to_subcribe_to.id
+                .title(language.tr("title.subscription"))
+                .description(language.tr_args(
+                    "subscribe.invalid-reminders",
+                    &[("max", MAX_REMINDER_DAYS.to_string())],
                 ))
                 .author(|author| {
                     author
@@ -193,10 +403,96 @@ pub async fn run_subscribe_command(
 
             return Ok(embed);
         }
+    };
+
+    if let Some(birthday) = Birthday::get(db, guild_id.0, user_to_subcribe_to.id.0)
+        .await
+        .map_err(CommandError::Db)?
+        .filter(|b| b.visibility() != Visibility::AnnouncementsOnly)
+    {
+        match Subscription::get(db, guild_id.0, user.id.0, birthday.id_birthday)
+            .await
+            .map_err(CommandError::Db)?
+        {
+            None if !birthday.accepts_subscriptions() => {
+                let embed = CreateEmbed(HashMap::new())
+                    .title(language.tr("title.subscription"))
+                    .description(language.tr_args(
+                        "subscribe.closed",
+                        &[("user", format!("<@{}>", user_to_subcribe_to.id))],
+                    ))
+                    .author(|author| {
+                        author
+                            .name(user.name.clone())
+                            .icon_url(utils::get_icon_url(user))
+                    })
+                    .to_owned();
+
+                return Ok(embed);
+            }
+            None => {
+                let mut subscription = Subscription::new(
+                    guild_id.0,
+                    user.id.0,
+                    birthday.id_birthday,
+                    notify_time,
+                    reminder_days.unwrap_or_else(|| vec![0]),
+                    Utc::now().naive_utc(),
+                );
+                subscription
+                    .insert(db, user.id.0)
+                    .await
+                    .map_err(CommandError::from_db)?;
+
+                let embed = CreateEmbed(HashMap::new())
+                    .title(language.tr("title.subscription"))
+                    .description(language.tr_args(
+                        "subscribe.subscribed",
+                        &[("user", format!("<@{}>", user_to_subcribe_to.id))],
+                    ))
+                    .author(|author| {
+                        author
+                            .name(user.name.clone())
+                            .icon_url(utils::get_icon_url(user))
+                    })
+                    .to_owned();
+
+                return Ok(embed);
+            }
+            Some(mut subscription) if notify_time.is_some() || reminder_days.is_some() => {
+                if notify_time.is_some() {
+                    subscription.notify_time = notify_time;
+                }
+                if let Some(reminder_days) = reminder_days {
+                    subscription.reminder_days = reminder_days;
+                }
+                subscription.modify_date = Some(Utc::now().naive_utc());
+                subscription
+                    .update(db, user.id.0)
+                    .await
+                    .map_err(CommandError::Db)?;
+
+                let embed = CreateEmbed(HashMap::new())
+                    .title(language.tr("title.subscription"))
+                    .description(language.tr_args(
+                        "subscribe.updated",
+                        &[("user", format!("<@{}>", user_to_subcribe_to.id))],
+                    ))
+                    .author(|author| {
+                        author
+                            .name(user.name.clone())
+                            .icon_url(utils::get_icon_url(user))
+                    })
+                    .to_owned();
+
+                return Ok(embed);
+            }
+            Some(_) => {}
+        }
 
         let embed = CreateEmbed(HashMap::new())
-            .title("Birthday Subscription:")
-            .description("You are already subscribed to this persons birthday.")
+            .title(language.tr("title.subscription"))
+            .description(language.tr("subscribe.already-subscribed"))
             .author(|author| {
                 author
                     .name(user.name.clone())
@@ -208,8 +504,8 @@ pub async fn run_subscribe_command(
     }
 
     let embed = CreateEmbed(HashMap::new())
-        .title("Birthday Subscription:")
-        .description("The targeted user does not provide a birthday.")
+        .title(language.tr("title.subscription"))
+        .description(language.tr("subscribe.no-birthday"))
         .author(|author| {
             author
                 .name(user.name.clone())
@@ -217,7 +513,7 @@ pub async fn run_subscribe_command(
         })
         .to_owned();
 
-    return Ok(embed);
+    Ok(embed)
 }
 
 pub async fn run_unsubscribe_command(
@@ -225,28 +521,29 @@ pub async fn run_unsubscribe_command(
     guild_id: &GuildId,
     user: &User,
     options: &[CommandDataOption],
+    language: Language,
 ) -> Result<CreateEmbed, CommandError> {
     let user_to_subcribe_to = UserInputParser
         .parse(options, 0)
-        .map_err(|x| CommandError::Parser(x))?;
+        .map_err(CommandError::Parser)?;
 
     if let Some(birthday) = Birthday::get(db, guild_id.0, user_to_subcribe_to.id.0)
         .await
-        .map_err(|x| CommandError::Db(x))?
+        .map_err(CommandError::Db)?
     {
         if let Some(subscription) =
             Subscription::get(db, guild_id.0, user.id.0, birthday.id_birthday)
                 .await
-                .map_err(|x| CommandError::Db(x))?
+                .map_err(CommandError::Db)?
         {
             subscription
-                .delete(db)
+                .delete(db, user.id.0)
                 .await
-                .map_err(|x| CommandError::Db(x))?;
+                .map_err(CommandError::Db)?;
 
             let embed = CreateEmbed(HashMap::new())
-                .title("Birthday Subscription:")
-                .description("Your subscriptions to this birthday has been deleted.")
+                .title(language.tr("title.subscription"))
+                .description(language.tr("unsubscribe.unsubscribed"))
                 .author(|author| {
                     author
                         .name(user.name.clone())
@@ -258,8 +555,8 @@ pub async fn run_unsubscribe_command(
         }
 
         let embed = CreateEmbed(HashMap::new())
-            .title("Birthday Subscription:")
-            .description("You have no subscription for this user.")
+            .title(language.tr("title.subscription"))
+            .description(language.tr("unsubscribe.not-subscribed"))
             .author(|author| {
                 author
                     .name(user.name.clone())
@@ -271,8 +568,8 @@ pub async fn run_unsubscribe_command(
     }
 
     let embed = CreateEmbed(HashMap::new())
-        .title("Birthday Subscription:")
-        .description("The user has no birthday set up.")
+        .title(language.tr("title.subscription"))
+        .description(language.tr("unsubscribe.no-birthday"))
         .author(|author| {
             author
                 .name(user.name.clone())
@@ -283,140 +580,1137 @@ pub async fn run_unsubscribe_command(
     Ok(embed)
 }
 
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    build_info_command(command);
-    build_set_command(command);
-    build_remove_command(command);
-    build_subscribe_command(command);
-    build_unsubscribe_command(command)
-}
+pub async fn run_subscriptions_command(
+    db: &PgPool,
+    ctx: &Context,
+    guild_id: &GuildId,
+    user: &User,
+    page: usize,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let birthdays: Vec<Birthday> = Birthday::get_all_by_subscriber(db, guild_id.0, user.id.0)
+        .await
+        .map_err(CommandError::Db)?
+        .into_iter()
+        .filter(|b| b.is_visible_to(user.id.0, true))
+        .collect();
 
-fn build_info_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
-        .name("birthday")
-        .description("A command for birthdays.")
-        .create_option(|sub_command| {
-            sub_command
-                .name("info")
-                .description("Gets the birthday of a user.")
-                .kind(CommandOptionType::SubCommand)
-        })
-}
+    if birthdays.is_empty() {
+        let embed = CreateEmbed(HashMap::new())
+            .title(language.tr("title.subscriptions"))
+            .description(language.tr("subscriptions.empty"))
+            .author(|author| {
+                author
+                    .name(user.name.clone())
+                    .icon_url(utils::get_icon_url(user))
+            })
+            .to_owned();
 
-fn build_set_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
-        .name("birthday")
-        .description("A command for birthdays.")
-        .create_option(|sub_command| {
-            sub_command
-                .name("set")
-                .description("Gets the birthday of a user.")
-                .kind(CommandOptionType::SubCommand)
-                .create_sub_option(|option| {
-                    option
-                        .name("day")
-                        .description("The day you were born.")
-                        .kind(CommandOptionType::Integer)
-                        .max_int_value(31)
-                        .min_int_value(1)
-                        .required(true)
-                })
-                .create_sub_option(|option| {
-                    option
-                        .name("month")
-                        .description("The month you were born.")
-                        .kind(CommandOptionType::Integer)
-                        .max_int_value(12)
-                        .min_int_value(1)
-                        .required(true)
-                })
-                .create_sub_option(|option| {
-                    option
-                        .name("year")
-                        .description("The year you were born.")
-                        .kind(CommandOptionType::Integer)
-                        .max_int_value(2100)
-                        .min_int_value(1900)
-                        .required(true)
-                })
-        })
-}
+        return Ok(embed.into());
+    }
 
-fn build_remove_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
-        .name("birthday")
-        .description("A command for birthdays.")
-        .create_option(|sub_command| {
-            sub_command
-                .name("remove")
-                .description("Removes all data of birthdays.")
-                .kind(CommandOptionType::SubCommand)
+    let now = Utc::now();
+    let settings = GuildSettings::get_or_default(db, guild_id.0, now.naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+
+    let user_ids: Vec<u64> = birthdays.iter().map(|b| b.user_id()).collect();
+    let timezones = UserSettings::get_timezones(db, &user_ids)
+        .await
+        .map_err(CommandError::Db)?;
+
+    let entries = gen_upcoming_entries(birthdays, &timezones, settings.leap_day_policy(), now);
+
+    let page_count = entries.len().div_ceil(SUBSCRIPTIONS_PER_PAGE);
+    let page = page.min(page_count - 1);
+
+    let fields = entries
+        .iter()
+        .skip(page * SUBSCRIPTIONS_PER_PAGE)
+        .take(SUBSCRIPTIONS_PER_PAGE)
+        .map(|entry| gen_upcoming_embed_field(ctx, guild_id.0, entry, language));
+
+    let fields: Vec<(String, String, bool)> = join_all(fields).await;
+
+    let mut embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.subscriptions"))
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
         })
+        .fields(fields)
+        .to_owned();
+
+    let mut components = CreateComponents::default();
+
+    if page_count > 1 {
+        embed.footer(|footer| {
+            footer.text(language.tr_args(
+                "pagination.page",
+                &[
+                    ("page", (page + 1).to_string()),
+                    ("count", page_count.to_string()),
+                ],
+            ))
+        });
+
+        components.create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .custom_id(format!("subscriptions:{}:{}", user.id, page.saturating_sub(1)))
+                    .label(language.tr("pagination.previous"))
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == 0)
+            })
+            .create_button(|button| {
+                button
+                    .custom_id(format!("subscriptions:{}:{}", user.id, page + 1))
+                    .label(language.tr("pagination.next"))
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page + 1 >= page_count)
+            })
+        });
+    }
+
+    Ok(CommandResponse { embed, components })
 }
 
-fn build_subscribe_command(
-    command: &mut CreateApplicationCommand,
-) -> &mut CreateApplicationCommand {
-    command
-        .name("birthday")
-        .description("A command for birthdays.")
-        .create_option(|sub_command| {
-            sub_command
-                .name("subscribe")
-                .description("Subscribes to the birthday of another user.")
-                .kind(CommandOptionType::SubCommand)
-                .create_sub_option(|option| {
-                    option
-                        .name("user")
-                        .description("The user you want to subscribe to.")
-                        .kind(CommandOptionType::User)
-                        .required(true)
-                })
+pub async fn run_privacy_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let hide_year = OptionalBooleanInputParser
+        .parse(options, "hide-year")
+        .map_err(CommandError::Parser)?;
+    let visibility = OptionalIntegerInputParser
+        .parse(options, "visibility")
+        .map_err(CommandError::Parser)?
+        .map(|value| {
+            Visibility::from_i32(value as i32).ok_or(CommandError::Parser(ParserError::Integer(
+                format!("Unknown visibility {}!", value),
+            )))
         })
-}
+        .transpose()?;
 
-fn build_unsubscribe_command(
-    command: &mut CreateApplicationCommand,
-) -> &mut CreateApplicationCommand {
-    command
-        .name("birthday")
-        .description("A command for birthdays.")
-        .create_option(|sub_command| {
-            sub_command
-                .name("unsubscribe")
-                .description("Unsubscribes from a user.")
-                .kind(CommandOptionType::SubCommand)
-                .create_sub_option(|option| {
-                    option
-                        .name("user")
-                        .description("The user you want to unsubscribe from.")
-                        .kind(CommandOptionType::User)
-                        .required(true)
-                })
+    let description = match Birthday::get(db, guild_id.0, user.id.0)
+        .await
+        .map_err(CommandError::Db)?
+    {
+        Some(mut birthday) => {
+            if hide_year.is_some() || visibility.is_some() {
+                if let Some(hide_year) = hide_year {
+                    birthday.hide_year = hide_year;
+                }
+                if let Some(visibility) = visibility {
+                    birthday.set_visibility(visibility);
+                }
+                birthday.modify_date = Some(Utc::now().naive_utc());
+                birthday.set_modified_by(user.id.0);
+                birthday.update(db, user.id.0).await.map_err(CommandError::Db)?;
+            }
+
+            gen_privacy_text(&birthday, language)
+        }
+        None => language.tr("info.not-registered"),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.privacy"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
         })
+        .to_owned();
+
+    Ok(embed)
 }
 
-async fn gen_embed_field(
-    db: &sqlx::Pool<sqlx::Postgres>,
-    guild_id: u64,
+pub async fn run_upcoming_command(
+    db: &PgPool,
     ctx: &Context,
-    subscription: &Subscription,
-) -> Result<(String, String, bool), CommandError> {
-    let birthday = Birthday::get_by_id(db, subscription.birthday_id)
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let days = OptionalIntegerInputParser
+        .parse(options, "days")
+        .map_err(CommandError::Parser)?
+        .unwrap_or(DEFAULT_UPCOMING_DAYS);
+
+    let birthdays = Birthday::get_all_by_guild(db, guild_id.0)
+        .await
+        .map_err(CommandError::Db)?;
+    let birthdays = filter_visible_birthdays(db, guild_id, user, birthdays).await?;
+
+    let now = Utc::now();
+    let settings = GuildSettings::get_or_default(db, guild_id.0, now.naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+
+    let user_ids: Vec<u64> = birthdays.iter().map(|b| b.user_id()).collect();
+    let timezones = UserSettings::get_timezones(db, &user_ids)
+        .await
+        .map_err(CommandError::Db)?;
+
+    let entries: Vec<(Birthday, NaiveDate, i64)> =
+        gen_upcoming_entries(birthdays, &timezones, settings.leap_day_policy(), now)
+            .into_iter()
+            .filter(|(_, _, days_remaining)| *days_remaining <= days)
+            .collect();
+
+    let fields = entries
+        .iter()
+        .take(UPCOMING_PER_PAGE)
+        .map(|entry| gen_upcoming_embed_field(ctx, guild_id.0, entry, language));
+
+    let fields: Vec<(String, String, bool)> = join_all(fields).await;
+
+    let description = match entries.len() {
+        0 => language.tr_args("upcoming.empty", &[("days", days.to_string())]),
+        n if n > UPCOMING_PER_PAGE => language.tr_args(
+            "upcoming.truncated",
+            &[
+                ("shown", UPCOMING_PER_PAGE.to_string()),
+                ("count", n.to_string()),
+                ("days", days.to_string()),
+            ],
+        ),
+        _ => language.tr_args("upcoming.list", &[("days", days.to_string())]),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.upcoming"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .fields(fields)
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_calendar_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let today = Utc::now().date_naive();
+
+    let month = OptionalIntegerInputParser
+        .parse(options, "month")
+        .map_err(CommandError::Parser)?
+        .map_or(today.month(), |month| month as u32);
+    let year = OptionalIntegerInputParser
+        .parse(options, "year")
+        .map_err(CommandError::Parser)?
+        .map_or(today.year(), |year| year as i32);
+
+    run_calendar_page(db, guild_id, user, year, month, language).await
+}
+
+pub async fn run_calendar_page(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    year: i32,
+    month: u32,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let first_day = NaiveDate::from_ymd_opt(year, month, 1).ok_or(CommandError::Parser(
+        ParserError::Integer(format!("Unknown month {}-{}!", year, month)),
+    ))?;
+
+    let birthdays = Birthday::get_all_by_guild(db, guild_id.0)
+        .await
+        .map_err(CommandError::Db)?;
+    let birthdays = filter_visible_birthdays(db, guild_id, user, birthdays).await?;
+    let settings = GuildSettings::get_or_default(db, guild_id.0, Utc::now().naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+
+    let mut days: BTreeMap<u32, Vec<u64>> = BTreeMap::new();
+    for birthday in birthdays.iter() {
+        if let Some(date) =
+            recurrence::occurrence_in_year(birthday.date.date(), year, settings.leap_day_policy())
+        {
+            if date.month() == month {
+                days.entry(date.day()).or_default().push(birthday.user_id());
+            }
+        }
+    }
+
+    let grid = format!("```\n{}\n```\n", gen_calendar_grid(first_day, &days, language));
+    let legend = gen_calendar_legend(
+        &days,
+        MAX_EMBED_DESCRIPTION_LENGTH.saturating_sub(grid.len()),
+        language,
+    );
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.calendar"))
+        .description(grid + &legend)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    let previous = first_day.pred_opt().unwrap_or(first_day);
+    let next = first_day
+        .checked_add_months(Months::new(1))
+        .unwrap_or(first_day);
+
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id(format!(
+                    "calendar:{}:{}:{}",
+                    user.id,
+                    previous.year(),
+                    previous.month()
+                ))
+                .label(language.tr("pagination.previous"))
+                .style(ButtonStyle::Secondary)
+                .disabled(previous == first_day)
+        })
+        .create_button(|button| {
+            button
+                .custom_id(format!("calendar:{}:{}:{}", user.id, next.year(), next.month()))
+                .label(language.tr("pagination.next"))
+                .style(ButtonStyle::Secondary)
+                .disabled(next == first_day)
+        })
+    });
+
+    Ok(CommandResponse { embed, components })
+}
+
+pub fn run_clear_all_command(
+    user: &User,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.clear-all"))
+        .description(language.tr("clear-all.question"))
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id(format!("clear-all:{}:confirm", user.id))
+                .label(language.tr("clear-all.button.confirm"))
+                .style(ButtonStyle::Danger)
+        })
+        .create_button(|button| {
+            button
+                .custom_id(format!("clear-all:{}:cancel", user.id))
+                .label(language.tr("clear-all.button.cancel"))
+                .style(ButtonStyle::Secondary)
+        })
+    });
+
+    Ok(CommandResponse { embed, components })
+}
+
+pub async fn run_clear_all_confirmation(
+    db: &PgPool,
+    user: &User,
+    confirmed: bool,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    if !confirmed {
+        let embed = CreateEmbed(HashMap::new())
+            .title(language.tr("title.clear-all"))
+            .description(language.tr("clear-all.cancelled"))
+            .author(|author| {
+                author
+                    .name(user.name.clone())
+                    .icon_url(utils::get_icon_url(user))
+            })
+            .to_owned();
+
+        return Ok(embed.into());
+    }
+
+    let erasure = UserDataErasure::execute(db, user.id.0)
+        .await
+        .map_err(CommandError::Db)?;
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.clear-all"))
+        .description(language.tr("clear-all.deleted"))
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .field(language.tr("clear-all.field.birthdays"), erasure.birthdays, true)
+        .field(language.tr("clear-all.field.subscriptions"), erasure.subscriptions, true)
+        .field(language.tr("clear-all.field.subscribers"), erasure.subscribers, true)
+        .field(language.tr("clear-all.field.notifications"), erasure.notifications, true)
+        .to_owned();
+
+    Ok(embed.into())
+}
+
+pub async fn run_export_command(
+    db: &PgPool,
+    ctx: &Context,
+    user: &User,
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let export = UserDataExport::collect(db, user.id.0, Utc::now().naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+    let data = serde_json::to_vec_pretty(&export).map_err(CommandError::Serialize)?;
+
+    let description = match user
+        .direct_message(ctx, |message| {
+            message
+                .content(language.tr("export.message"))
+                .add_file(AttachmentType::Bytes {
+                    data: Cow::from(data),
+                    filename: String::from("birthdaybot-export.json"),
+                })
+        })
+        .await
+    {
+        Ok(_) => language.tr("export.sent"),
+        Err(_) => language.tr("export.failed"),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.export"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+/// Creates a new link to the calendar feed of the user's subscriptions, which replaces the old one.
+///
+/// The link is sent via direct message, as anyone knowing it can read the feed.
+pub async fn run_calendar_link_command(
+    db: &PgPool,
+    ctx: &Context,
+    guild_id: &GuildId,
+    user: &User,
+    calendar_url: Option<&str>,
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let description = match calendar_url {
+        Some(calendar_url) => {
+            let token = CalendarToken::gen_token();
+            let link = format!(
+                "{}{}{}.ics",
+                calendar_url.trim_end_matches('/'),
+                CALENDAR_PATH,
+                token
+            );
+
+            // The old link keeps working if the new one can not be delivered.
+            match user
+                .direct_message(ctx, |message| {
+                    message.content(language.tr_args("calendar-link.message", &[("link", link)]))
+                })
+                .await
+            {
+                Ok(_) => {
+                    CalendarToken::new(guild_id.0, user.id.0, &token, Utc::now().naive_utc())
+                        .save(db)
+                        .await
+                        .map_err(CommandError::Db)?;
+
+                    language.tr("calendar-link.sent")
+                }
+                Err(_) => language.tr("calendar-link.failed"),
+            }
+        }
+        None => language.tr("calendar-link.disabled"),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.calendar-link"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_timezone_command(
+    db: &PgPool,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let timezone = match TimezoneInputParser.parse(options, 0) {
+        Ok(timezone) => timezone,
+        Err(_) => {
+            let embed = CreateEmbed(HashMap::new())
+                .title(language.tr("title.birthday"))
+                .description(language.tr("timezone.unknown"))
+                .author(|author| {
+                    author
+                        .name(user.name.clone())
+                        .icon_url(utils::get_icon_url(user))
+                })
+                .to_owned();
+
+            return Ok(embed);
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+
+    let mut settings = UserSettings::get_or_default(db, user.id.0, now)
+        .await
+        .map_err(CommandError::Db)?;
+    settings.set_timezone(timezone);
+    settings.modify_date = Some(now);
+    settings.save(db).await.map_err(CommandError::Db)?;
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.birthday"))
+        .description(
+            language.tr_args("timezone.set", &[("timezone", timezone.name().to_string())]),
+        )
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub fn run_timezone_autocomplete(options: &[CommandDataOption]) -> Vec<&'static str> {
+    let input = options
+        .iter()
+        .find(|o| o.focused)
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    TZ_VARIANTS
+        .iter()
+        .map(|tz| tz.name())
+        .filter(|name| name.to_lowercase().contains(&input))
+        .take(25)
+        .collect()
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    build_info_command(command);
+    build_set_command(command);
+    build_remove_command(command);
+    build_subscribe_command(command);
+    build_unsubscribe_command(command);
+    build_timezone_command(command);
+    build_subscriptions_command(command);
+    build_clear_all_command(command);
+    build_export_command(command);
+    build_upcoming_command(command);
+    build_calendar_command(command);
+    build_privacy_command(command);
+    build_calendar_link_command(command)
+}
+
+fn build_info_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("info")
+                .description("Gets the birthday of a user.")
+                .localize("command.birthday.info")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("user")
+                        .description("The user whose birthday should be shown, yourself by default.")
+                        .localize("command.birthday.info.user")
+                        .kind(CommandOptionType::User)
+                        .required(false)
+                })
+        })
+}
+
+fn build_set_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("set")
+                .description("Sets your birthday.")
+                .localize("command.birthday.set")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("day")
+                        .description("The day you were born.")
+                        .localize("command.birthday.set.day")
+                        .kind(CommandOptionType::Integer)
+                        .max_int_value(31)
+                        .min_int_value(1)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("month")
+                        .description("The month you were born.")
+                        .localize("command.birthday.set.month")
+                        .kind(CommandOptionType::Integer)
+                        .max_int_value(12)
+                        .min_int_value(1)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("year")
+                        .description("The year you were born, leave empty to keep your age private.")
+                        .localize("command.birthday.set.year")
+                        .kind(CommandOptionType::Integer)
+                        .max_int_value(2100)
+                        .min_int_value(1900)
+                        .required(false)
+                })
+        })
+}
+
+fn build_remove_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("remove")
+                .description("Removes all data of birthdays.")
+                .localize("command.birthday.remove")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+fn build_subscribe_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("subscribe")
+                .description("Subscribes to the birthday of another user.")
+                .localize("command.birthday.subscribe")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("user")
+                        .description("The user you want to subscribe to.")
+                        .localize("command.birthday.subscribe.user")
+                        .kind(CommandOptionType::User)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("time")
+                        .description("The time (HH:MM) in your timezone when you want to be notified.")
+                        .localize("command.birthday.subscribe.time")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("reminders")
+                        .description("Days before the birthday you want to be notified, e.g. 7,1,0.")
+                        .localize("command.birthday.subscribe.reminders")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+}
+
+fn build_unsubscribe_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("unsubscribe")
+                .description("Unsubscribes from a user.")
+                .localize("command.birthday.unsubscribe")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("user")
+                        .description("The user you want to unsubscribe from.")
+                        .localize("command.birthday.unsubscribe.user")
+                        .kind(CommandOptionType::User)
+                        .required(true)
+                })
+        })
+}
+
+fn build_timezone_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("timezone")
+                .description("Sets your timezone, used for your birthday and the time of your subscriptions.")
+                .localize("command.birthday.timezone")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("timezone")
+                        .description("The IANA name of your timezone, e.g. Europe/Berlin.")
+                        .localize("command.birthday.timezone.timezone")
+                        .kind(CommandOptionType::String)
+                        .set_autocomplete(true)
+                        .required(true)
+                })
+        })
+}
+
+fn build_subscriptions_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("subscriptions")
+                .description("Shows all birthdays you are subscribed to.")
+                .localize("command.birthday.subscriptions")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+fn build_clear_all_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("clear-all")
+                .description("Deletes all data the bot has about you.")
+                .localize("command.birthday.clear-all")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+fn build_export_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("export")
+                .description("Sends you a copy of all data the bot has about you.")
+                .localize("command.birthday.export")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+fn build_calendar_link_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("calendar-link")
+                .description("Sends you a new link to a calendar of your subscriptions, the old link stops working.")
+                .localize("command.birthday.calendar-link")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+fn build_upcoming_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("upcoming")
+                .description("Shows the next birthdays on this server.")
+                .localize("command.birthday.upcoming")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("days")
+                        .description("How many days to look ahead, 30 by default.")
+                        .localize("command.birthday.upcoming.days")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .max_int_value(366)
+                        .required(false)
+                })
+        })
+}
+
+fn build_calendar_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("calendar")
+                .description("Shows the birthdays on this server in a monthly calendar.")
+                .localize("command.birthday.calendar")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("month")
+                        .description("The month to show, the current month by default.")
+                        .localize("command.birthday.calendar.month")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .max_int_value(12)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("year")
+                        .description("The year to show, the current year by default.")
+                        .localize("command.birthday.calendar.year")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .max_int_value(9999)
+                        .required(false)
+                })
+        })
+}
+
+fn build_privacy_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday")
+        .description("A command for birthdays.")
+        .localize("command.birthday")
+        .create_option(|sub_command| {
+            sub_command
+                .name("privacy")
+                .description("Shows or changes who can see your birthday.")
+                .localize("command.birthday.privacy")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("hide-year")
+                        .description("Hides your birth year from other users.")
+                        .localize("command.birthday.privacy.hide-year")
+                        .kind(CommandOptionType::Boolean)
+                        .required(false)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("visibility")
+                        .description("Who can see your birthday.")
+                        .localize("command.birthday.privacy.visibility")
+                        .kind(CommandOptionType::Integer)
+                        .add_int_choice_localized(
+                            "Everyone",
+                            Visibility::Everyone as i32,
+                            i18n::localizations("command.birthday.privacy.visibility.everyone"),
+                        )
+                        .add_int_choice_localized(
+                            "Subscribers only",
+                            Visibility::SubscribersOnly as i32,
+                            i18n::localizations(
+                                "command.birthday.privacy.visibility.subscribers-only",
+                            ),
+                        )
+                        .add_int_choice_localized(
+                            "Announcements only",
+                            Visibility::AnnouncementsOnly as i32,
+                            i18n::localizations(
+                                "command.birthday.privacy.visibility.announcements-only",
+                            ),
+                        )
+                        .required(false)
+                })
+        })
+}
+
+/// Renders a month as a grid starting on monday, with a `*` next to every day on which birthdays are celebrated.
+fn gen_calendar_grid(
+    first_day: NaiveDate,
+    days: &BTreeMap<u32, Vec<u64>>,
+    language: Language,
+) -> String {
+    let title = format!(
+        "{} {}",
+        language.tr(&format!("month.{}", first_day.month())),
+        first_day.year()
+    );
+    let mut grid = format!("{:^28}\n", title);
+    grid.push_str(&language.tr("calendar.weekdays"));
+    grid.push('\n');
+
+    let offset = first_day.weekday().num_days_from_monday() as usize;
+    grid.push_str(&" ".repeat(offset * 4));
+
+    let mut day = first_day;
+    while day.month() == first_day.month() {
+        let marker = if days.contains_key(&day.day()) { '*' } else { ' ' };
+        grid.push_str(&format!("{:>3}{}", day.day(), marker));
+
+        if day.weekday() == Weekday::Sun {
+            grid.push('\n');
+        }
+
+        day = match day.succ_opt() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    grid.trim_end().to_string()
+}
+
+/// Lists the birthdays of every day, those which do not fit into `max_length` are only counted.
+fn gen_calendar_legend(
+    days: &BTreeMap<u32, Vec<u64>>,
+    max_length: usize,
+    language: Language,
+) -> String {
+    let total: usize = days.values().map(|user_ids| user_ids.len()).sum();
+    // Leaves room for the line about the birthdays which are left out.
+    let max_length = max_length.saturating_sub(
+        language
+            .tr_args("calendar.more", &[("count", total.to_string())])
+            .len()
+            + 1,
+    );
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut length = 0;
+    let mut shown = 0;
+
+    'days: for (day, user_ids) in days {
+        let mut line = format!("`{:>2}`", day);
+
+        for (index, user_id) in user_ids.iter().enumerate() {
+            let separator = if index == 0 { " " } else { ", " };
+            let mention = format!("{}<@{}>", separator, user_id);
+
+            if length + line.len() + mention.len() + 1 > max_length {
+                if index > 0 {
+                    lines.push(line);
+                }
+                break 'days;
+            }
+
+            line.push_str(&mention);
+            shown += 1;
+        }
+
+        length += line.len() + 1;
+        lines.push(line);
+    }
+
+    if shown < total {
+        lines.push(language.tr_args("calendar.more", &[("count", (total - shown).to_string())]));
+    }
+
+    lines.join("\n")
+}
+
+/// Computes the next occurrence of every birthday and the days remaining until then, sorted by the latter.
+fn gen_upcoming_entries(
+    birthdays: Vec<Birthday>,
+    timezones: &HashMap<u64, Tz>,
+    policy: LeapDayPolicy,
+    now: DateTime<Utc>,
+) -> Vec<(Birthday, NaiveDate, i64)> {
+    let mut entries: Vec<(Birthday, NaiveDate, i64)> = birthdays
+        .into_iter()
+        .map(|birthday| {
+            let timezone = timezones.get(&birthday.user_id()).copied().unwrap_or(Tz::UTC);
+            let today = now.with_timezone(&timezone).date_naive();
+            let next = recurrence::next_occurrence(birthday.date.date(), today, policy);
+            let days_remaining = (next - today).num_days();
+
+            (birthday, next, days_remaining)
+        })
+        .collect();
+    entries.sort_by_key(|(_, _, days_remaining)| *days_remaining);
+
+    entries
+}
+
+async fn gen_upcoming_embed_field(
+    ctx: &Context,
+    guild_id: u64,
+    (birthday, next, days_remaining): &(Birthday, NaiveDate, i64),
+    language: Language,
+) -> (String, String, bool) {
+    let name = match ctx.http.get_member(guild_id, birthday.user_id()).await {
+        Ok(m) => m.display_name().to_string(),
+        Err(_) => format!("<@{}>:", birthday.user_id()),
+    };
+
+    (
+        name,
+        format!("{} ({})", next, gen_days_remaining_text(*days_remaining, language)),
+        false,
+    )
+}
+
+/// Removes all birthdays the user is not allowed to see.
+async fn filter_visible_birthdays(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    birthdays: Vec<Birthday>,
+) -> Result<Vec<Birthday>, CommandError> {
+    let subscribed: Vec<i32> = Birthday::get_all_by_subscriber(db, guild_id.0, user.id.0)
         .await
-        .map_err(|x| CommandError::Db(x))?
+        .map_err(CommandError::Db)?
+        .iter()
+        .map(|b| b.id_birthday)
+        .collect();
+
+    Ok(birthdays
+        .into_iter()
+        .filter(|b| b.is_visible_to(user.id.0, subscribed.contains(&b.id_birthday)))
+        .collect())
+}
+
+fn gen_privacy_text(birthday: &Birthday, language: Language) -> String {
+    let visibility = match birthday.visibility() {
+        Visibility::Everyone => "visibility.everyone",
+        Visibility::SubscribersOnly => "visibility.subscribers-only",
+        Visibility::AnnouncementsOnly => "visibility.announcements-only",
+    };
+    let year = match birthday.hide_year {
+        true => "privacy.year.hidden",
+        false => "privacy.year.shown",
+    };
+
+    language.tr_args(
+        "privacy.text",
+        &[
+            ("visibility", language.tr(visibility)),
+            ("year", language.tr(year)),
+        ],
+    )
+}
+
+fn gen_days_remaining_text(days_remaining: i64, language: Language) -> String {
+    match days_remaining {
+        0 => language.tr("days.today"),
+        1 => language.tr("days.tomorrow"),
+        _ => language.tr_args("days.in", &[("days", days_remaining.to_string())]),
+    }
+}
+
+async fn gen_embed_field(
+    db: &sqlx::Pool<sqlx::Postgres>,
+    guild_id: u64,
+    ctx: &Context,
+    subscription: &Subscription,
+    language: Language,
+) -> Result<(String, String, bool), CommandError> {
+    let birthday = Birthday::get_by_id(db, subscription.birthday_id)
+        .await
+        .map_err(CommandError::Db)?
         .expect("Birthday should not be delete before subscription.");
 
+    let date = if birthday.is_visible_to(subscription.user_id(), true) {
+        birthday.display_date()
+    } else {
+        language.tr("subscriptions.private")
+    };
+
     match ctx.http.get_member(guild_id, birthday.user_id()).await {
-        Ok(m) => Ok((
-            m.display_name().to_string(),
-            birthday.date.date().to_string(),
-            false,
-        )),
-        Err(_) => Ok((
-            format!("<@{}>:", birthday.user_id()),
-            birthday.date.date().to_string(),
-            false,
-        )),
+        Ok(m) => Ok((m.display_name().to_string(), date, false)),
+        Err(_) => Ok((format!("<@{}>:", birthday.user_id()), date, false)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calendar_legend_lists_every_day() {
+        let days = BTreeMap::from([(3, vec![1, 2]), (17, vec![3])]);
+
+        assert_eq!(
+            gen_calendar_legend(&days, MAX_EMBED_DESCRIPTION_LENGTH, Language::English),
+            "` 3` <@1>, <@2>\n`17` <@3>"
+        );
+    }
+
+    #[test]
+    fn calendar_legend_counts_birthdays_which_do_not_fit() {
+        let user_ids: Vec<u64> = (0..1000).map(|id| 100_000_000_000_000_000 + id).collect();
+        let days = BTreeMap::from([(1, user_ids.clone()), (2, user_ids)]);
+
+        let legend = gen_calendar_legend(&days, MAX_EMBED_DESCRIPTION_LENGTH, Language::English);
+        let shown = legend.matches("<@").count();
+
+        assert!(legend.len() <= MAX_EMBED_DESCRIPTION_LENGTH);
+        assert!(legend.ends_with(&format!("\nand {} more", 2000 - shown)));
     }
 }
@@ -0,0 +1,1298 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Datelike, Duration};
+use chrono_tz::Tz;
+use serde_json::Value;
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
+use serenity::futures::future::join_all;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::component::ButtonStyle;
+use serenity::model::prelude::interaction::application_command::CommandDataOption;
+use serenity::model::prelude::{AttachmentType, ChannelType, GuildId};
+use serenity::model::user::User;
+use serenity::model::Permissions;
+use serenity::prelude::Context;
+use sqlx::types::chrono::Utc;
+use sqlx::PgPool;
+
+use crate::i18n::{self, Language, Localize};
+use crate::ical::{self, BirthdayEvent};
+use crate::models::audit_log::AuditLog;
+use crate::models::birthday::{Birthday, Visibility};
+use crate::models::guild_settings::{GuildSettings, MAX_MILESTONE_AGE};
+use crate::models::pending_import::{ImportRow, PendingImport};
+use crate::models::user_settings::UserSettings;
+use crate::recurrence::LeapDayPolicy;
+use crate::template::{self, TemplateValues, DEFAULT_ANNOUNCEMENT_TEMPLATE};
+use crate::utils;
+
+use super::import::{self, MAX_IMPORT_ROWS};
+use super::parser::{
+    AttachmentInputParser, DateInputParser, IntegerInputParser, OptionalChannelInputParser,
+    OptionalMilestoneInputParser, OptionalRoleInputParser, OptionalStringInputParser,
+    OptionalUserInputParser, ParserError, StringInputParser, UserInputParser,
+};
+use super::{CommandError, CommandResponse};
+
+const TEMPLATE_KIND_DM: &str = "dm";
+const TEMPLATE_KIND_ANNOUNCEMENT: &str = "announcement";
+const EXPORT_FORMAT_CSV: &str = "csv";
+const EXPORT_FORMAT_ICS: &str = "ics";
+/// The age shown in template previews.
+const PREVIEW_AGE: i32 = 30;
+/// Fewer entries than other pages, as every entry can list long changes like templates.
+const AUDIT_ENTRIES_PER_PAGE: usize = 5;
+const MAX_EMBED_FIELD_LENGTH: usize = 1024;
+/// Keys of the stored rows which change with every update and are left out of the listed changes.
+const AUDIT_IGNORED_KEYS: [&str; 5] = [
+    "id",
+    "id_guild_settings",
+    "create_date",
+    "modify_date",
+    "modified_by",
+];
+/// The maximum size of an imported file in bytes.
+const MAX_IMPORT_FILE_SIZE: u64 = 1024 * 1024;
+/// How long an import can be confirmed after the file was checked.
+const IMPORT_EXPIRY_MINUTES: i64 = 60;
+
+pub async fn run_leap_day_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let value = IntegerInputParser
+        .parse(options, 0)
+        .map_err(CommandError::Parser)?;
+    let policy = LeapDayPolicy::from_i32(value as i32).ok_or(CommandError::Parser(
+        ParserError::Integer(format!("Unknown leap day policy {}!", value)),
+    ))?;
+
+    let mut settings = GuildSettings::get_or_default(db, guild_id.0, Utc::now().naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+    settings.set_leap_day_policy(policy);
+    settings.modify_date = Some(Utc::now().naive_utc());
+    settings.save(db, user.id.0).await.map_err(CommandError::Db)?;
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.settings"))
+        .description(language.tr_args(
+            "leap-day.set",
+            &[("policy", gen_leap_day_policy_text(policy, language))],
+        ))
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_channel_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let channel = OptionalChannelInputParser
+        .parse(options, "channel")
+        .map_err(CommandError::Parser)?;
+
+    let mut settings = GuildSettings::get_or_default(db, guild_id.0, Utc::now().naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+    settings.set_announcement_channel_id(channel.as_ref().map(|c| c.id.0));
+    settings.modify_date = Some(Utc::now().naive_utc());
+    settings.save(db, user.id.0).await.map_err(CommandError::Db)?;
+
+    let description = match channel {
+        Some(channel) => {
+            language.tr_args("channel.set", &[("channel", format!("<#{}>", channel.id))])
+        }
+        None => language.tr("channel.removed"),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.settings"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_role_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let role = OptionalRoleInputParser
+        .parse(options, "role")
+        .map_err(CommandError::Parser)?;
+
+    let mut settings = GuildSettings::get_or_default(db, guild_id.0, Utc::now().naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+    settings.set_birthday_role_id(role.as_ref().map(|r| r.id.0));
+    settings.modify_date = Some(Utc::now().naive_utc());
+    settings.save(db, user.id.0).await.map_err(CommandError::Db)?;
+
+    let description = match role {
+        Some(role) => language.tr_args("role.set", &[("role", format!("<@&{}>", role.id))]),
+        None => language.tr("role.removed"),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.settings"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_milestones_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let milestones = match OptionalMilestoneInputParser.parse(options, "ages") {
+        Ok(milestones) => milestones.unwrap_or_default(),
+        Err(_) => {
+            let embed = CreateEmbed(HashMap::new())
+                .title(language.tr("title.settings"))
+                .description(language.tr_args(
+                    "milestones.invalid",
+                    &[("max", MAX_MILESTONE_AGE.to_string())],
+                ))
+                .author(|author| {
+                    author
+                        .name(user.name.clone())
+                        .icon_url(utils::get_icon_url(user))
+                })
+                .to_owned();
+
+            return Ok(embed);
+        }
+    };
+
+    let mut settings = GuildSettings::get_or_default(db, guild_id.0, Utc::now().naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+    settings.milestones = milestones;
+    settings.modify_date = Some(Utc::now().naive_utc());
+    settings.save(db, user.id.0).await.map_err(CommandError::Db)?;
+
+    let description = match settings.milestones.is_empty() {
+        true => language.tr("milestones.removed"),
+        false => {
+            let ages: Vec<String> = settings.milestones.iter().map(|a| a.to_string()).collect();
+            language.tr_args("milestones.set", &[("ages", ages.join(", "))])
+        }
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.settings"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_template_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let kind = StringInputParser
+        .parse(options, 0)
+        .map_err(CommandError::Parser)?;
+    let text = OptionalStringInputParser
+        .parse(options, "text")
+        .map_err(CommandError::Parser)?;
+
+    if let Some(Err(why)) = text.as_deref().map(template::validate) {
+        let embed = CreateEmbed(HashMap::new())
+            .title(language.tr("title.settings"))
+            .description(why.description(language))
+            .author(|author| {
+                author
+                    .name(user.name.clone())
+                    .icon_url(utils::get_icon_url(user))
+            })
+            .to_owned();
+
+        return Ok(embed);
+    }
+
+    let mut settings = GuildSettings::get_or_default(db, guild_id.0, Utc::now().naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+
+    match kind.as_str() {
+        TEMPLATE_KIND_DM => settings.dm_template = text.clone(),
+        _ => settings.announcement_template = text.clone(),
+    }
+
+    settings.modify_date = Some(Utc::now().naive_utc());
+    settings.save(db, user.id.0).await.map_err(CommandError::Db)?;
+
+    let text_key = match text {
+        Some(_) => "template.updated",
+        None => "template.reset",
+    };
+    let description =
+        language.tr_args(text_key, &[("kind", gen_template_kind_text(&kind, language))]);
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.settings"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_template_preview_command(
+    db: &PgPool,
+    ctx: &Context,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let kind = StringInputParser
+        .parse(options, 0)
+        .map_err(CommandError::Parser)?;
+
+    let settings = GuildSettings::get_or_default(db, guild_id.0, Utc::now().naive_utc())
+        .await
+        .map_err(CommandError::Db)?;
+
+    let custom_template = match kind.as_str() {
+        TEMPLATE_KIND_DM => settings.dm_template,
+        _ => settings.announcement_template,
+    };
+
+    let values = TemplateValues {
+        mention: format!("<@{}>", user.id),
+        name: user.name.clone(),
+        age: Some(PREVIEW_AGE),
+        date: Utc::now().date_naive(),
+        when: language.tr("days.today"),
+        guild: utils::get_guild_name(ctx, *guild_id).await,
+    };
+
+    let description = match (custom_template, kind.as_str()) {
+        (Some(custom_template), _) => template::render(&custom_template, &values)
+            .unwrap_or_else(|| language.tr("template.render-failed")),
+        (None, TEMPLATE_KIND_DM) => language.tr("template.no-dm-template"),
+        (None, _) => template::render(DEFAULT_ANNOUNCEMENT_TEMPLATE, &values).unwrap_or_default(),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr_args(
+            "title.preview",
+            &[("kind", gen_template_kind_text(&kind, language))],
+        ))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+/// Sets the birthday of another member, e.g. when moving over from another bot.
+pub async fn run_set_member_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let target = UserInputParser
+        .parse(options, 0)
+        .map_err(CommandError::Parser)?;
+    let (date, year) = DateInputParser
+        .parse(options)
+        .map_err(CommandError::Parser)?;
+
+    let description = if target.bot {
+        language.tr("set-member.bot")
+    } else if let Some(mut birthday) = Birthday::get(db, guild_id.0, target.id.0)
+        .await
+        .map_err(CommandError::Db)?
+    {
+        birthday.date = date;
+        birthday.year = year;
+        birthday.modify_date = Some(Utc::now().naive_utc());
+        birthday.set_modified_by(user.id.0);
+        birthday.update(db, user.id.0).await.map_err(CommandError::Db)?;
+
+        language.tr_args(
+            "set-member.updated",
+            &[
+                ("user", format!("<@{}>", target.id)),
+                ("date", birthday.format_date(true)),
+            ],
+        )
+    } else {
+        let mut birthday =
+            Birthday::new(guild_id.0, target.id.0, date, year, Utc::now().naive_utc());
+        birthday.set_modified_by(user.id.0);
+        birthday.insert(db, user.id.0).await.map_err(CommandError::from_db)?;
+
+        language.tr_args(
+            "set-member.set",
+            &[
+                ("user", format!("<@{}>", target.id)),
+                ("date", birthday.format_date(true)),
+            ],
+        )
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.member-birthday"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_remove_member_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let target = UserInputParser
+        .parse(options, 0)
+        .map_err(CommandError::Parser)?;
+
+    let description = match Birthday::get(db, guild_id.0, target.id.0)
+        .await
+        .map_err(CommandError::Db)?
+    {
+        Some(birthday) => {
+            birthday.delete(db, user.id.0).await.map_err(CommandError::Db)?;
+
+            language.tr_args("remove-member.removed", &[("user", format!("<@{}>", target.id))])
+        }
+        None => language.tr_args(
+            "remove-member.not-registered",
+            &[("user", format!("<@{}>", target.id))],
+        ),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.member-birthday"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub async fn run_audit_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let target = OptionalUserInputParser
+        .parse(options, "user")
+        .map_err(CommandError::Parser)?;
+
+    run_audit_page(db, guild_id, user, target.map(|t| t.id.0), 0, language).await
+}
+
+/// Shows a page of the audit log, `target_id` limits it to the changes about one user.
+pub async fn run_audit_page(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    target_id: Option<u64>,
+    page: usize,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let count = AuditLog::count(db, guild_id.0, target_id)
+        .await
+        .map_err(CommandError::Db)? as usize;
+
+    if count == 0 {
+        let embed = CreateEmbed(HashMap::new())
+            .title(language.tr("title.audit"))
+            .description(language.tr("audit.empty"))
+            .author(|author| {
+                author
+                    .name(user.name.clone())
+                    .icon_url(utils::get_icon_url(user))
+            })
+            .to_owned();
+
+        return Ok(embed.into());
+    }
+
+    let page_count = count.div_ceil(AUDIT_ENTRIES_PER_PAGE);
+    let page = page.min(page_count - 1);
+
+    let entries = AuditLog::get_page(
+        db,
+        guild_id.0,
+        target_id,
+        AUDIT_ENTRIES_PER_PAGE as i64,
+        (page * AUDIT_ENTRIES_PER_PAGE) as i64,
+    )
+    .await
+    .map_err(CommandError::Db)?;
+
+    let description = match target_id {
+        Some(target_id) => {
+            language.tr_args("audit.member", &[("user", format!("<@{}>", target_id))])
+        }
+        None => language.tr("audit.guild"),
+    };
+
+    let mut embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.audit"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .fields(entries.iter().map(|entry| gen_audit_embed_field(entry, language)))
+        .to_owned();
+
+    let mut components = CreateComponents::default();
+
+    if page_count > 1 {
+        let target_id = target_id.unwrap_or_default();
+
+        embed.footer(|footer| {
+            footer.text(language.tr_args(
+                "pagination.page",
+                &[
+                    ("page", (page + 1).to_string()),
+                    ("count", page_count.to_string()),
+                ],
+            ))
+        });
+
+        components.create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .custom_id(format!(
+                        "audit:{}:{}:{}",
+                        user.id,
+                        target_id,
+                        page.saturating_sub(1)
+                    ))
+                    .label(language.tr("pagination.previous"))
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == 0)
+            })
+            .create_button(|button| {
+                button
+                    .custom_id(format!("audit:{}:{}:{}", user.id, target_id, page + 1))
+                    .label(language.tr("pagination.next"))
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page + 1 >= page_count)
+            })
+        });
+    }
+
+    Ok(CommandResponse { embed, components })
+}
+
+/// Checks an uploaded csv file and shows which rows would be imported, the import
+/// itself happens after the confirmation in [`run_import_confirmation`].
+pub async fn run_import_command(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let attachment = AttachmentInputParser
+        .parse(options, 0)
+        .map_err(CommandError::Parser)?;
+
+    let text = match attachment.size <= MAX_IMPORT_FILE_SIZE {
+        true => attachment
+            .download()
+            .await
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok()),
+        false => None,
+    };
+
+    let rows = match text {
+        Some(text) => import::parse_csv(text.trim_start_matches('\u{feff}')),
+        None => {
+            return Ok(gen_import_embed(user, &language.tr("import.unreadable"), language).into())
+        }
+    };
+
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Ok(gen_import_embed(
+            user,
+            &language.tr_args("import.too-many-rows", &[("max", MAX_IMPORT_ROWS.to_string())]),
+            language,
+        )
+        .into());
+    }
+
+    let row_count = rows.len();
+    let mut valid_rows: Vec<ImportRow> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    for (number, row) in rows {
+        match row {
+            Ok(row) => valid_rows.push(row),
+            Err(why) => errors.push(language.tr_args(
+                "import.line",
+                &[
+                    ("number", number.to_string()),
+                    ("error", why.description(language)),
+                ],
+            )),
+        }
+    }
+
+    let existing: HashSet<u64> = Birthday::get_all_by_guild(db, guild_id.0)
+        .await
+        .map_err(CommandError::Db)?
+        .iter()
+        .map(|b| b.user_id())
+        .collect();
+    let replaced = valid_rows
+        .iter()
+        .filter(|row| existing.contains(&row.user_id))
+        .count();
+
+    let description = match valid_rows.len() {
+        0 => language.tr_args("import.none", &[("count", row_count.to_string())]),
+        n => language.tr_args(
+            "import.preview",
+            &[
+                ("valid", n.to_string()),
+                ("count", row_count.to_string()),
+                ("replaced", replaced.to_string()),
+            ],
+        ),
+    };
+
+    let mut embed = gen_import_embed(user, &description, language);
+
+    if !errors.is_empty() {
+        embed.field(
+            language.tr("import.field.errors"),
+            gen_truncated_text(errors.join("\n")),
+            false,
+        );
+    }
+
+    let mut components = CreateComponents::default();
+
+    if !valid_rows.is_empty() {
+        let now = Utc::now().naive_utc();
+        let mut pending_import = PendingImport::new(guild_id.0, user.id.0, &valid_rows, now);
+        pending_import
+            .insert(db, now - Duration::minutes(IMPORT_EXPIRY_MINUTES))
+            .await
+            .map_err(CommandError::Db)?;
+
+        components.create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .custom_id(format!(
+                        "import:{}:{}:confirm",
+                        user.id, pending_import.id_pending_import
+                    ))
+                    .label(language.tr("import.button.confirm"))
+                    .style(ButtonStyle::Success)
+            })
+            .create_button(|button| {
+                button
+                    .custom_id(format!(
+                        "import:{}:{}:cancel",
+                        user.id, pending_import.id_pending_import
+                    ))
+                    .label(language.tr("import.button.cancel"))
+                    .style(ButtonStyle::Secondary)
+            })
+        });
+    }
+
+    Ok(CommandResponse { embed, components })
+}
+
+pub async fn run_import_confirmation(
+    db: &PgPool,
+    guild_id: &GuildId,
+    user: &User,
+    import_id: i32,
+    confirmed: bool,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let now = Utc::now().naive_utc();
+    let pending_import = PendingImport::get(db, import_id, guild_id.0, user.id.0)
+        .await
+        .map_err(CommandError::Db)?
+        .filter(|i| i.create_date >= now - Duration::minutes(IMPORT_EXPIRY_MINUTES));
+
+    let description = match (pending_import, confirmed) {
+        (None, _) => language.tr("import.expired"),
+        (Some(pending_import), false) => {
+            pending_import
+                .delete(db)
+                .await
+                .map_err(CommandError::Db)?;
+
+            language.tr("import.cancelled")
+        }
+        (Some(pending_import), true) => {
+            let count = pending_import
+                .commit(db, now)
+                .await
+                .map_err(CommandError::from_db)?;
+
+            language.tr_args("import.imported", &[("count", count.to_string())])
+        }
+    };
+
+    Ok(gen_import_embed(user, &description, language).into())
+}
+
+/// Sends all birthdays of the guild which are visible to everyone as a file via direct message.
+///
+/// Hidden birth years are left out, the csv file can be imported again with `/birthday-admin import`.
+pub async fn run_export_guild_command(
+    db: &PgPool,
+    ctx: &Context,
+    guild_id: &GuildId,
+    user: &User,
+    options: &[CommandDataOption],
+    language: Language,
+) -> Result<CreateEmbed, CommandError> {
+    let format = StringInputParser
+        .parse(options, 0)
+        .map_err(CommandError::Parser)?;
+
+    let mut birthdays = Birthday::get_all_by_guild(db, guild_id.0)
+        .await
+        .map_err(CommandError::Db)?;
+    let total = birthdays.len();
+
+    birthdays.retain(|b| b.visibility() == Visibility::Everyone);
+    birthdays.sort_by_key(|b| (b.date.month(), b.date.day(), b.user_id()));
+
+    let (data, filename) = match format.as_str() {
+        EXPORT_FORMAT_ICS => {
+            let settings = GuildSettings::get_or_default(db, guild_id.0, Utc::now().naive_utc())
+                .await
+                .map_err(CommandError::Db)?;
+            let names = join_all(
+                birthdays
+                    .iter()
+                    .map(|b| utils::get_member_name(&ctx.http, *guild_id, b.user_id())),
+            )
+            .await;
+
+            let events: Vec<BirthdayEvent> = birthdays
+                .iter()
+                .zip(names)
+                .map(|(birthday, name)| BirthdayEvent::from_birthday(birthday, &name))
+                .collect();
+
+            let calendar = ical::gen_calendar(
+                &utils::get_guild_name(ctx, *guild_id).await,
+                &events,
+                settings.leap_day_policy(),
+                Utc::now().naive_utc(),
+            );
+
+            (calendar, "birthdays.ics")
+        }
+        _ => {
+            let user_ids: Vec<u64> = birthdays.iter().map(|b| b.user_id()).collect();
+            let timezones = UserSettings::get_timezones(db, &user_ids)
+                .await
+                .map_err(CommandError::Db)?;
+
+            (gen_export_csv(&birthdays, &timezones), "birthdays.csv")
+        }
+    };
+
+    let description = match user
+        .direct_message(ctx, |message| {
+            message
+                .content(language.tr("guild-export.message"))
+                .add_file(AttachmentType::Bytes {
+                    data: Cow::from(data.into_bytes()),
+                    filename: String::from(filename),
+                })
+        })
+        .await
+    {
+        Ok(_) => language.tr_args(
+            "guild-export.sent",
+            &[
+                ("count", birthdays.len().to_string()),
+                ("hidden", (total - birthdays.len()).to_string()),
+            ],
+        ),
+        Err(_) => language.tr("export.failed"),
+    };
+
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.guild-export"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned();
+
+    Ok(embed)
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    build_leap_day_command(command);
+    build_channel_command(command);
+    build_role_command(command);
+    build_milestones_command(command);
+    build_template_command(command);
+    build_template_preview_command(command);
+    build_set_member_command(command);
+    build_remove_member_command(command);
+    build_audit_command(command);
+    build_import_command(command);
+    build_export_command(command)
+}
+
+fn build_leap_day_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("leap-day")
+                .description("Sets when birthdays on the 29th of February are celebrated in non-leap years.")
+                .localize("command.birthday-admin.leap-day")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("policy")
+                        .description("The day on which the birthday is celebrated.")
+                        .localize("command.birthday-admin.leap-day.policy")
+                        .kind(CommandOptionType::Integer)
+                        .add_int_choice_localized(
+                            "28th of February",
+                            LeapDayPolicy::February28 as i32,
+                            i18n::localizations("command.birthday-admin.leap-day.policy.february-28"),
+                        )
+                        .add_int_choice_localized(
+                            "1st of March",
+                            LeapDayPolicy::March1 as i32,
+                            i18n::localizations("command.birthday-admin.leap-day.policy.march-1"),
+                        )
+                        .add_int_choice_localized(
+                            "Only in leap years",
+                            LeapDayPolicy::LeapYearsOnly as i32,
+                            i18n::localizations(
+                                "command.birthday-admin.leap-day.policy.leap-years-only",
+                            ),
+                        )
+                        .required(true)
+                })
+        })
+}
+
+fn build_channel_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("channel")
+                .description("Sets the channel in which birthdays are announced.")
+                .localize("command.birthday-admin.channel")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("channel")
+                        .description("The channel for announcements, leave empty to disable them.")
+                        .localize("command.birthday-admin.channel.channel")
+                        .kind(CommandOptionType::Channel)
+                        .channel_types(&[ChannelType::Text, ChannelType::News])
+                        .required(false)
+                })
+        })
+}
+
+fn build_role_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("role")
+                .description("Sets the role members receive for the duration of their birthday.")
+                .localize("command.birthday-admin.role")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("role")
+                        .description("The birthday role, leave empty to disable it.")
+                        .localize("command.birthday-admin.role.role")
+                        .kind(CommandOptionType::Role)
+                        .required(false)
+                })
+        })
+}
+
+fn build_milestones_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("milestones")
+                .description("Sets the ages which are highlighted as milestone birthdays.")
+                .localize("command.birthday-admin.milestones")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("ages")
+                        .description("A comma separated list of ages, leave empty to disable milestones.")
+                        .localize("command.birthday-admin.milestones.ages")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+}
+
+fn build_template_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("template")
+                .description("Sets the text of birthday messages.")
+                .localize("command.birthday-admin.template")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("kind")
+                        .description("The message the template is used for.")
+                        .localize("command.birthday-admin.template.kind")
+                        .kind(CommandOptionType::String)
+                        .add_string_choice_localized(
+                            "Direct messages",
+                            TEMPLATE_KIND_DM,
+                            i18n::localizations("command.birthday-admin.template.kind.dm"),
+                        )
+                        .add_string_choice_localized(
+                            "Announcements",
+                            TEMPLATE_KIND_ANNOUNCEMENT,
+                            i18n::localizations("command.birthday-admin.template.kind.announcement"),
+                        )
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("text")
+                        .description("The text with placeholders like {mention} or {age}, leave empty to reset it.")
+                        .localize("command.birthday-admin.template.text")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+}
+
+fn build_template_preview_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("template-preview")
+                .description("Shows a birthday message as it would be sent for you.")
+                .localize("command.birthday-admin.template-preview")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("kind")
+                        .description("The message to preview.")
+                        .localize("command.birthday-admin.template-preview.kind")
+                        .kind(CommandOptionType::String)
+                        .add_string_choice_localized(
+                            "Direct messages",
+                            TEMPLATE_KIND_DM,
+                            i18n::localizations("command.birthday-admin.template-preview.kind.dm"),
+                        )
+                        .add_string_choice_localized(
+                            "Announcements",
+                            TEMPLATE_KIND_ANNOUNCEMENT,
+                            i18n::localizations("command.birthday-admin.template-preview.kind.announcement"),
+                        )
+                        .required(true)
+                })
+        })
+}
+
+fn build_set_member_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("set")
+                .description("Sets the birthday of a member.")
+                .localize("command.birthday-admin.set")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("user")
+                        .description("The member whose birthday should be set.")
+                        .localize("command.birthday-admin.set.user")
+                        .kind(CommandOptionType::User)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("day")
+                        .description("The day the member was born.")
+                        .localize("command.birthday-admin.set.day")
+                        .kind(CommandOptionType::Integer)
+                        .max_int_value(31)
+                        .min_int_value(1)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("month")
+                        .description("The month the member was born.")
+                        .localize("command.birthday-admin.set.month")
+                        .kind(CommandOptionType::Integer)
+                        .max_int_value(12)
+                        .min_int_value(1)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("year")
+                        .description("The year the member was born, leave empty if unknown.")
+                        .localize("command.birthday-admin.set.year")
+                        .kind(CommandOptionType::Integer)
+                        .max_int_value(2100)
+                        .min_int_value(1900)
+                        .required(false)
+                })
+        })
+}
+
+fn build_remove_member_command(
+    command: &mut CreateApplicationCommand,
+) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("remove")
+                .description("Removes the birthday of a member and all subscriptions to it.")
+                .localize("command.birthday-admin.remove")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("user")
+                        .description("The member whose birthday should be removed.")
+                        .localize("command.birthday-admin.remove.user")
+                        .kind(CommandOptionType::User)
+                        .required(true)
+                })
+        })
+}
+
+fn build_audit_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("audit")
+                .description("Shows who changed birthdays, subscriptions and settings on this server.")
+                .localize("command.birthday-admin.audit")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("user")
+                        .description("Only show changes to the data of this user.")
+                        .localize("command.birthday-admin.audit.user")
+                        .kind(CommandOptionType::User)
+                        .required(false)
+                })
+        })
+}
+
+fn build_import_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("import")
+                .description("Imports birthdays from a csv file after a confirmation.")
+                .localize("command.birthday-admin.import")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("file")
+                        .description("A csv file with the columns user, day, month, year and timezone.")
+                        .localize("command.birthday-admin.import.file")
+                        .kind(CommandOptionType::Attachment)
+                        .required(true)
+                })
+        })
+}
+
+fn build_export_command(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("birthday-admin")
+        .description("A command for managing birthdays on this server.")
+        .localize("command.birthday-admin")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub_command| {
+            sub_command
+                .name("export")
+                .description("Sends you all birthdays on this server which are visible to everyone.")
+                .localize("command.birthday-admin.export")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .name("format")
+                        .description("The format of the file.")
+                        .localize("command.birthday-admin.export.format")
+                        .kind(CommandOptionType::String)
+                        .add_string_choice("CSV", EXPORT_FORMAT_CSV)
+                        .add_string_choice("iCalendar", EXPORT_FORMAT_ICS)
+                        .required(true)
+                })
+        })
+}
+
+/// Generates a csv file in the format of `/birthday-admin import`.
+///
+/// The timezone is left empty for members who have not set one.
+fn gen_export_csv(birthdays: &[Birthday], timezones: &HashMap<u64, Tz>) -> String {
+    let mut csv = String::from("user_id,day,month,year,timezone\n");
+
+    for birthday in birthdays {
+        let year = match birthday.year {
+            Some(year) if !birthday.hide_year => year.to_string(),
+            _ => String::new(),
+        };
+
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            birthday.user_id(),
+            birthday.date.day(),
+            birthday.date.month(),
+            year,
+            timezones
+                .get(&birthday.user_id())
+                .map(|timezone| timezone.name())
+                .unwrap_or_default()
+        ));
+    }
+
+    csv
+}
+
+fn gen_import_embed(user: &User, description: &str, language: Language) -> CreateEmbed {
+    CreateEmbed(HashMap::new())
+        .title(language.tr("title.import"))
+        .description(description)
+        .author(|author| {
+            author
+                .name(user.name.clone())
+                .icon_url(utils::get_icon_url(user))
+        })
+        .to_owned()
+}
+
+fn gen_audit_embed_field(entry: &AuditLog, language: Language) -> (String, String, bool) {
+    let action = language.tr(
+        entry
+            .action()
+            .map_or("audit.action.unknown", |action| action.text_key()),
+    );
+    let actor = format!("<@{}>", entry.actor_id());
+
+    let mut lines = vec![match entry.target_id() {
+        Some(target_id) => language.tr_args(
+            "audit.by-for",
+            &[("actor", actor), ("target", format!("<@{}>", target_id))],
+        ),
+        None => language.tr_args("audit.by", &[("actor", actor)]),
+    }];
+    lines.extend(gen_audit_changes(
+        entry.before_data.as_ref(),
+        entry.after_data.as_ref(),
+    ));
+
+    (
+        format!("{} {}", entry.create_date.format("%Y-%m-%d %H:%M"), action),
+        gen_truncated_text(lines.join("\n")),
+        false,
+    )
+}
+
+/// Shortens a text to the length of an embed field.
+fn gen_truncated_text(mut text: String) -> String {
+    if text.chars().count() > MAX_EMBED_FIELD_LENGTH {
+        text = text.chars().take(MAX_EMBED_FIELD_LENGTH - 3).collect();
+        text.push_str("...");
+    }
+
+    text
+}
+
+/// Lists every value which differs between the stored rows, one line per key.
+fn gen_audit_changes(before: Option<&Value>, after: Option<&Value>) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut keys: Vec<&String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|key| !AUDIT_IGNORED_KEYS.contains(&key.as_str()))
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| before.get(*key) != after.get(*key))
+        .map(|key| {
+            format!(
+                "`{}`: {} → {}",
+                key,
+                gen_audit_value(before.get(key)),
+                gen_audit_value(after.get(key))
+            )
+        })
+        .collect()
+}
+
+fn gen_audit_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::from("-"),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn gen_template_kind_text(kind: &str, language: Language) -> String {
+    match kind {
+        TEMPLATE_KIND_DM => language.tr("template.kind.dm"),
+        _ => language.tr("template.kind.announcement"),
+    }
+}
+
+fn gen_leap_day_policy_text(policy: LeapDayPolicy, language: Language) -> String {
+    match policy {
+        LeapDayPolicy::February28 => language.tr("leap-day.february-28"),
+        LeapDayPolicy::March1 => language.tr("leap-day.march-1"),
+        LeapDayPolicy::LeapYearsOnly => language.tr("leap-day.leap-years-only"),
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+
+use chrono_tz::Tz;
+
+use crate::i18n::Language;
+use crate::models::pending_import::ImportRow;
+
+use super::parser::{parse_date, MAX_BIRTH_YEAR, MIN_BIRTH_YEAR};
+
+/// The maximum number of rows a single file can contain.
+pub const MAX_IMPORT_ROWS: usize = 1000;
+/// The accepted names of each column in the optional header line, the export writes the first ones.
+const HEADER_NAMES: [&[&str]; 5] = [
+    &["user_id", "user"],
+    &["day"],
+    &["month"],
+    &["year"],
+    &["timezone"],
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportError {
+    Columns,
+    User(String),
+    Number(String),
+    Date,
+    Timezone(String),
+    Duplicate(u64),
+}
+
+impl ImportError {
+    pub fn description(&self, language: Language) -> String {
+        match self {
+            ImportError::Columns => language.tr("import.columns"),
+            ImportError::User(value) => {
+                language.tr_args("import.user", &[("value", value.clone())])
+            }
+            ImportError::Number(value) => {
+                language.tr_args("import.number", &[("value", value.clone())])
+            }
+            ImportError::Date => language.tr_args(
+                "import.date",
+                &[
+                    ("min", MIN_BIRTH_YEAR.to_string()),
+                    ("max", MAX_BIRTH_YEAR.to_string()),
+                ],
+            ),
+            ImportError::Timezone(value) => {
+                language.tr_args("import.timezone", &[("value", value.clone())])
+            }
+            ImportError::Duplicate(user_id) => {
+                language.tr_args("import.duplicate", &[("user", format!("<@{}>", user_id))])
+            }
+        }
+    }
+}
+
+/// Parses a csv file with the columns user, day, month, year and timezone into numbered rows.
+///
+/// Columns are separated by commas, semicolons or tabs and may be quoted, year and timezone
+/// may be left empty or out. A first line with the column names is skipped as header.
+pub fn parse_csv(text: &str) -> Vec<(usize, Result<ImportRow, ImportError>)> {
+    let mut user_ids = HashSet::new();
+
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, split_fields(line)))
+        .filter(|(_, fields)| fields.iter().any(|field| !field.is_empty()))
+        .filter(|(number, fields)| *number != 1 || !is_header(fields))
+        .map(|(number, fields)| {
+            let row = parse_row(&fields).and_then(|row| match user_ids.insert(row.user_id) {
+                true => Ok(row),
+                false => Err(ImportError::Duplicate(row.user_id)),
+            });
+
+            (number, row)
+        })
+        .collect()
+}
+
+fn parse_row(fields: &[String]) -> Result<ImportRow, ImportError> {
+    if !(3..=5).contains(&fields.len()) {
+        return Err(ImportError::Columns);
+    }
+
+    let user_id = parse_user(&fields[0])?;
+    let day = parse_number(&fields[1])?;
+    let month = parse_number(&fields[2])?;
+    let year = match fields.get(3).filter(|field| !field.is_empty()) {
+        Some(field) => Some(parse_number(field)?),
+        None => None,
+    };
+
+    let (date, year) = parse_date(day, month, year).map_err(|_| ImportError::Date)?;
+
+    let timezone = match fields.get(4).filter(|field| !field.is_empty()) {
+        Some(field) => Some(
+            field
+                .parse::<Tz>()
+                .map(|timezone| timezone.name().to_string())
+                .map_err(|_| ImportError::Timezone(field.clone()))?,
+        ),
+        None => None,
+    };
+
+    Ok(ImportRow {
+        user_id,
+        date,
+        year,
+        timezone,
+    })
+}
+
+/// Accepts a plain user id or a mention like `<@123>` or `<@!123>`.
+fn parse_user(field: &str) -> Result<u64, ImportError> {
+    let id = field
+        .strip_prefix("<@")
+        .and_then(|rest| rest.strip_suffix('>'))
+        .map(|rest| rest.trim_start_matches('!'))
+        .unwrap_or(field);
+
+    id.parse::<u64>()
+        .ok()
+        .filter(|id| *id != 0)
+        .ok_or_else(|| ImportError::User(field.to_string()))
+}
+
+fn parse_number(field: &str) -> Result<i64, ImportError> {
+    field
+        .parse::<i64>()
+        .map_err(|_| ImportError::Number(field.to_string()))
+}
+
+fn is_header(fields: &[String]) -> bool {
+    (3..=5).contains(&fields.len())
+        && fields
+            .iter()
+            .zip(HEADER_NAMES)
+            .all(|(field, names)| names.iter().any(|name| field.eq_ignore_ascii_case(name)))
+}
+
+/// Splits a line at separators outside of quotes, a doubled quote within quotes is kept as one.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut is_quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if is_quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => is_quoted = !is_quoted,
+            ',' | ';' | '\t' if !is_quoted => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+
+    fields.push(field.trim().to_string());
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_ids(rows: &[(usize, Result<ImportRow, ImportError>)]) -> Vec<u64> {
+        rows.iter()
+            .filter_map(|(_, row)| row.as_ref().ok().map(|row| row.user_id))
+            .collect()
+    }
+
+    #[test]
+    fn skips_a_header_with_the_column_names() {
+        let rows = parse_csv("User_ID;Day;Month;Year;Timezone\n1;17;5;1990;Europe/Berlin\n");
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].0, 2);
+        assert_eq!(user_ids(&rows), vec![1]);
+    }
+
+    #[test]
+    fn reports_an_invalid_first_row() {
+        let rows = parse_csv("someone,17,5\n2,18,5\n");
+
+        assert!(matches!(&rows[0], (1, Err(ImportError::User(value))) if value == "someone"));
+        assert_eq!(user_ids(&rows), vec![2]);
+    }
+
+    #[test]
+    fn splits_quoted_fields() {
+        assert_eq!(
+            split_fields(r#""<@1>", "a, b" ;"say ""hi""",x"#),
+            vec!["<@1>", "a, b", r#"say "hi""#, "x"]
+        );
+    }
+
+    #[test]
+    fn parses_rows_with_optional_columns() {
+        let rows = parse_csv("\"<@!1>\",\"17\",\"5\"\n2\t29\t2\t\tUTC\n\n3,1,1,2000");
+
+        assert_eq!(user_ids(&rows), vec![1, 2, 3]);
+        assert_eq!(rows[0].1.as_ref().unwrap().year, None);
+        assert_eq!(rows[1].1.as_ref().unwrap().timezone.as_deref(), Some("UTC"));
+        assert_eq!(rows[2].0, 4);
+    }
+
+    #[test]
+    fn rejects_invalid_rows() {
+        let rows = parse_csv("1,29,2,2023\n2,1,1,1,UTC,x\n3,x,1\n4,1,1,,Mars/Base\n4,1,1\n");
+        let errors: Vec<ImportError> = rows.into_iter().filter_map(|(_, row)| row.err()).collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                ImportError::Date,
+                ImportError::Columns,
+                ImportError::Number(String::from("x")),
+                ImportError::Timezone(String::from("Mars/Base")),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_users() {
+        let rows = parse_csv("1,1,1\n<@1>,2,2\n");
+
+        assert!(matches!(rows[1], (2, Err(ImportError::Duplicate(1)))));
+    }
+}
@@ -1,10 +1,49 @@
+use serenity::builder::{CreateComponents, CreateEmbed};
+
 use self::parser::ParserError;
 
 pub mod birthday;
+pub mod birthday_admin;
+mod import;
 mod parser;
 
+/// The postgres error code for unique constraint violations.
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// The embed and message components a command or component interaction responds with.
+pub struct CommandResponse {
+    pub embed: CreateEmbed,
+    pub components: CreateComponents,
+}
+
+impl From<CreateEmbed> for CommandResponse {
+    fn from(embed: CreateEmbed) -> Self {
+        CommandResponse {
+            embed,
+            components: CreateComponents::default(),
+        }
+    }
+}
+
+// The values are only read by the `Debug` output when an error is logged.
+#[allow(dead_code)]
 #[derive(Debug)]
 pub enum CommandError {
     Db(sqlx::Error),
     Parser(ParserError),
+    Serialize(serde_json::Error),
+    AlreadyExists,
+}
+
+impl CommandError {
+    /// Maps unique constraint violations to [`CommandError::AlreadyExists`], all other errors to [`CommandError::Db`].
+    pub fn from_db(why: sqlx::Error) -> CommandError {
+        if let sqlx::Error::Database(db_error) = &why {
+            if db_error.code().as_deref() == Some(UNIQUE_VIOLATION) {
+                return CommandError::AlreadyExists;
+            }
+        }
+
+        CommandError::Db(why)
+    }
 }
@@ -1,13 +1,37 @@
+use chrono::Datelike;
+use chrono_tz::Tz;
 use serenity::model::{
-    prelude::interaction::application_command::{CommandDataOption, CommandDataOptionValue},
+    prelude::{
+        interaction::application_command::{CommandDataOption, CommandDataOptionValue},
+        Attachment, PartialChannel, Role,
+    },
     user::User,
 };
-use sqlx::types::chrono::{NaiveDate, NaiveDateTime};
+use sqlx::types::chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 
+use crate::models::birthday::PLACEHOLDER_YEAR;
+use crate::models::guild_settings::MAX_MILESTONE_AGE;
+use crate::models::subscription::MAX_REMINDER_DAYS;
+
+pub const MIN_BIRTH_YEAR: i64 = 1900;
+pub const MAX_BIRTH_YEAR: i64 = 2100;
+
+// The values are only read by the `Debug` output when an error is logged.
+#[allow(dead_code)]
 #[derive(Debug)]
 pub enum ParserError {
     Date,
     User(String),
+    Integer(String),
+    Timezone(String),
+    Time(String),
+    Reminder(String),
+    Channel(String),
+    Role(String),
+    Boolean(String),
+    Milestone(String),
+    Text(String),
+    Attachment(String),
 }
 
 pub struct UserInputParser;
@@ -26,44 +50,376 @@ impl UserInputParser {
             return Err(ParserError::User(String::from("No option found!")));
         }
 
-        return Err(ParserError::User(format!(
+        Err(ParserError::User(format!(
             "No option found at index {}!",
             index
-        )));
+        )))
     }
 }
 
-pub struct DateInputParser;
+pub struct OptionalUserInputParser;
 
-impl DateInputParser {
-    pub fn parse(&self, options: &[CommandDataOption]) -> Result<NaiveDateTime, ParserError> {
-        let date_parts: Result<Vec<i64>, String> =
-            (0..3).map(|x| Self::get_int_option(options, x)).collect();
+impl OptionalUserInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<User>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::User(data, _)) = option.resolved.as_ref() {
+                return Ok(Some(data.clone()));
+            }
 
-        let date_parts = date_parts.expect("User input expected.");
+            return Err(ParserError::User(String::from("No value found!")));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct StringInputParser;
+
+impl StringInputParser {
+    pub fn parse(&self, options: &[CommandDataOption], index: usize) -> Result<String, ParserError> {
+        if let Some(option) = options.get(index) {
+            if let Some(CommandDataOptionValue::String(data)) = option.resolved.as_ref() {
+                return Ok(data.clone());
+            }
+
+            return Err(ParserError::Text(String::from("No value found!")));
+        }
+
+        Err(ParserError::Text(format!("No option found at index {}!", index)))
+    }
+}
+
+pub struct OptionalStringInputParser;
 
-        if let Some(date) = NaiveDate::from_ymd_opt(
-            date_parts[2] as i32,
-            date_parts[1] as u32,
-            date_parts[0] as u32,
-        ) {
-            if let Some(date) = date.and_hms_opt(0, 0, 0) {
-                return Ok(date);
+impl OptionalStringInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<String>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::String(data)) = option.resolved.as_ref() {
+                return Ok(Some(data.clone()));
             }
+
+            return Err(ParserError::Text(String::from("No value found!")));
         }
 
-        Err(ParserError::Date)
+        Ok(None)
     }
+}
+
+pub struct AttachmentInputParser;
+
+impl AttachmentInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        index: usize,
+    ) -> Result<Attachment, ParserError> {
+        if let Some(option) = options.get(index) {
+            if let Some(CommandDataOptionValue::Attachment(data)) = option.resolved.as_ref() {
+                return Ok(data.clone());
+            }
+
+            return Err(ParserError::Attachment(String::from("No value found!")));
+        }
+
+        Err(ParserError::Attachment(format!(
+            "No option found at index {}!",
+            index
+        )))
+    }
+}
+
+pub struct IntegerInputParser;
 
-    fn get_int_option(options: &[CommandDataOption], index: usize) -> Result<i64, String> {
+impl IntegerInputParser {
+    pub fn parse(&self, options: &[CommandDataOption], index: usize) -> Result<i64, ParserError> {
         if let Some(option) = options.get(index) {
             if let Some(value) = option.resolved.as_ref() {
                 if let CommandDataOptionValue::Integer(data) = value {
                     return Ok(*data);
                 }
+
+                return Err(ParserError::Integer(String::from("No value found!")));
+            }
+
+            return Err(ParserError::Integer(String::from("No option found!")));
+        }
+
+        Err(ParserError::Integer(format!(
+            "No option found at index {}!",
+            index
+        )))
+    }
+}
+
+pub struct OptionalIntegerInputParser;
+
+impl OptionalIntegerInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<i64>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::Integer(data)) = option.resolved.as_ref() {
+                return Ok(Some(*data));
+            }
+
+            return Err(ParserError::Integer(String::from("No value found!")));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct OptionalBooleanInputParser;
+
+impl OptionalBooleanInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<bool>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::Boolean(data)) = option.resolved.as_ref() {
+                return Ok(Some(*data));
+            }
+
+            return Err(ParserError::Boolean(String::from("No value found!")));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct TimezoneInputParser;
+
+impl TimezoneInputParser {
+    pub fn parse(&self, options: &[CommandDataOption], index: usize) -> Result<Tz, ParserError> {
+        if let Some(option) = options.get(index) {
+            if let Some(value) = option.resolved.as_ref() {
+                if let CommandDataOptionValue::String(data) = value {
+                    return data
+                        .parse::<Tz>()
+                        .map_err(|_| ParserError::Timezone(format!("Unknown timezone {}!", data)));
+                }
+
+                return Err(ParserError::Timezone(String::from("No value found!")));
+            }
+
+            return Err(ParserError::Timezone(String::from("No option found!")));
+        }
+
+        Err(ParserError::Timezone(format!(
+            "No option found at index {}!",
+            index
+        )))
+    }
+}
+
+pub struct OptionalTimeInputParser;
+
+impl OptionalTimeInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<NaiveTime>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::String(data)) = option.resolved.as_ref() {
+                return NaiveTime::parse_from_str(data, "%H:%M")
+                    .map(Some)
+                    .map_err(|_| ParserError::Time(format!("Invalid time {}!", data)));
+            }
+
+            return Err(ParserError::Time(String::from("No value found!")));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct OptionalReminderInputParser;
+
+impl OptionalReminderInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<Vec<i32>>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::String(data)) = option.resolved.as_ref() {
+                let mut days = data
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|part| !part.is_empty())
+                    .map(|part| match part.parse::<i32>() {
+                        Ok(day) if (0..=MAX_REMINDER_DAYS).contains(&day) => Ok(day),
+                        _ => Err(ParserError::Reminder(format!("Invalid reminder {}!", part))),
+                    })
+                    .collect::<Result<Vec<i32>, ParserError>>()?;
+
+                if days.is_empty() {
+                    return Err(ParserError::Reminder(String::from("No reminders found!")));
+                }
+
+                days.sort_unstable_by(|a, b| b.cmp(a));
+                days.dedup();
+
+                return Ok(Some(days));
+            }
+
+            return Err(ParserError::Reminder(String::from("No value found!")));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct OptionalMilestoneInputParser;
+
+impl OptionalMilestoneInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<Vec<i32>>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::String(data)) = option.resolved.as_ref() {
+                let mut ages = data
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|part| !part.is_empty())
+                    .map(|part| match part.parse::<i32>() {
+                        Ok(age) if (1..=MAX_MILESTONE_AGE).contains(&age) => Ok(age),
+                        _ => Err(ParserError::Milestone(format!("Invalid age {}!", part))),
+                    })
+                    .collect::<Result<Vec<i32>, ParserError>>()?;
+
+                ages.sort_unstable();
+                ages.dedup();
+
+                return Ok(Some(ages));
+            }
+
+            return Err(ParserError::Milestone(String::from("No value found!")));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct OptionalChannelInputParser;
+
+impl OptionalChannelInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<PartialChannel>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::Channel(data)) = option.resolved.as_ref() {
+                return Ok(Some(data.clone()));
             }
+
+            return Err(ParserError::Channel(String::from("No value found!")));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct OptionalRoleInputParser;
+
+impl OptionalRoleInputParser {
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+        name: &str,
+    ) -> Result<Option<Role>, ParserError> {
+        if let Some(option) = find_option(options, name) {
+            if let Some(CommandDataOptionValue::Role(data)) = option.resolved.as_ref() {
+                return Ok(Some(data.clone()));
+            }
+
+            return Err(ParserError::Role(String::from("No value found!")));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct DateInputParser;
+
+impl DateInputParser {
+    /// Parses the `day`, `month` and optional `year` options into the date and the year.
+    ///
+    /// The date always falls into [`PLACEHOLDER_YEAR`], the birth year is only returned separately.
+    pub fn parse(
+        &self,
+        options: &[CommandDataOption],
+    ) -> Result<(NaiveDateTime, Option<i32>), ParserError> {
+        let date_parts: Result<Vec<i64>, String> = ["day", "month"]
+            .iter()
+            .map(|name| Self::get_int_option(options, name))
+            .collect();
+
+        let date_parts = date_parts.expect("User input expected.");
+
+        let year = match find_option(options, "year").and_then(|o| o.resolved.as_ref()) {
+            Some(CommandDataOptionValue::Integer(year)) => Some(*year),
+            Some(_) => return Err(ParserError::Date),
+            None => None,
+        };
+
+        parse_date(date_parts[0], date_parts[1], year)
+    }
+
+    fn get_int_option(options: &[CommandDataOption], name: &str) -> Result<i64, String> {
+        if let Some(CommandDataOptionValue::Integer(data)) =
+            find_option(options, name).and_then(|option| option.resolved.as_ref())
+        {
+            return Ok(*data);
         }
 
-        Err(format!("Option {} not found!", index))
+        Err(format!("Option {} not found!", name))
     }
 }
+
+/// Checks the parts of a birthday and builds the date and the year stored for it.
+///
+/// These are the rules of every birthday input, the command options apply the same limits.
+/// The date is checked against the birth year but stored in [`PLACEHOLDER_YEAR`], so that
+/// the year is kept in one place only.
+pub fn parse_date(
+    day: i64,
+    month: i64,
+    year: Option<i64>,
+) -> Result<(NaiveDateTime, Option<i32>), ParserError> {
+    if let Some(year) = year {
+        if !(MIN_BIRTH_YEAR..=MAX_BIRTH_YEAR).contains(&year) {
+            return Err(ParserError::Date);
+        }
+    }
+
+    let year = year.map(|year| year as i32);
+    let (day, month) = match (u32::try_from(day), u32::try_from(month)) {
+        (Ok(day), Ok(month)) => (day, month),
+        _ => return Err(ParserError::Date),
+    };
+
+    NaiveDate::from_ymd_opt(year.unwrap_or(PLACEHOLDER_YEAR), month, day)
+        .and_then(|date| date.with_year(PLACEHOLDER_YEAR))
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| (date, year))
+        .ok_or(ParserError::Date)
+}
+
+fn find_option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a CommandDataOption> {
+    options.iter().find(|o| o.name == name)
+}
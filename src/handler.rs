@@ -7,18 +7,22 @@ use std::{
     time::Duration,
 };
 
-use chrono::Datelike;
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, TimeZone};
+use chrono_tz::Tz;
 use serenity::{
     async_trait,
     builder::CreateEmbed,
+    http::HttpError,
     model::prelude::{
         command::Command,
         interaction::{
-            application_command::ApplicationCommandInteraction, Interaction,
+            application_command::ApplicationCommandInteraction,
+            message_component::MessageComponentInteraction, Interaction,
             InteractionResponseType,
-        }, Message, Ready, ResumedEvent,
+        }, ChannelId, GuildId, Message, Ready, ResumedEvent,
     },
-    prelude::{Context, EventHandler},
+    prelude::{Context, EventHandler, SerenityError},
+    utils::Colour,
 };
 use sqlx::{
     types::chrono::Utc,
@@ -30,17 +34,42 @@ use crate::{
     commands::{
         self,
         birthday::{
-            run_info_command, run_remove_command, run_set_command, run_subscribe_command,
-            run_unsubscribe_command,
+            run_calendar_command, run_calendar_link_command, run_calendar_page,
+            run_clear_all_command, run_clear_all_confirmation, run_export_command,
+            run_info_command, run_privacy_command, run_remove_command, run_set_command,
+            run_subscribe_command, run_subscribe_button, run_subscriptions_command,
+            run_timezone_autocomplete, run_timezone_command, run_unsubscribe_command,
+            run_upcoming_command,
         },
-        CommandError,
+        birthday_admin::{
+            run_audit_command, run_audit_page, run_channel_command, run_export_guild_command,
+            run_import_command, run_import_confirmation, run_leap_day_command,
+            run_milestones_command, run_remove_member_command, run_role_command,
+            run_set_member_command, run_template_command, run_template_preview_command,
+        },
+        CommandError, CommandResponse,
+    },
+    models::{
+        announcement::SendAnnouncement,
+        birthday::{Birthday, Visibility},
+        guild_settings::GuildSettings,
+        role_assignment::RoleAssignment,
+        scheduler_state::SchedulerState,
+        subscription::{SendNotification, Subscription, MAX_REMINDER_DAYS},
+        user_settings::UserSettings,
     },
-    models::{birthday::Birthday, subscription::{Subscription, SendNotification}},
+    i18n::Language,
+    recurrence,
+    template::{self, TemplateValues, DEFAULT_ANNOUNCEMENT_TEMPLATE},
+    utils,
 };
 
 pub struct Handler {
     pub database: sqlx::PgPool,
     pub is_loop_running: AtomicBool,
+    pub catch_up_days: u32,
+    /// The public url of the calendar server, `None` if it is not running.
+    pub calendar_url: Option<String>,
 }
 
 #[async_trait]
@@ -54,6 +83,87 @@ impl EventHandler for Handler {
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::Autocomplete(autocomplete) = &interaction {
+            let choices = match autocomplete.data.options.first() {
+                Some(subcommand)
+                    if autocomplete.data.name == "birthday" && subcommand.name == "timezone" =>
+                {
+                    run_timezone_autocomplete(&subcommand.options)
+                }
+                _ => Vec::new(),
+            };
+
+            if let Err(why) = autocomplete
+                .create_autocomplete_response(&ctx.http, |response| {
+                    for choice in choices {
+                        response.add_string_choice(choice, choice);
+                    }
+
+                    response
+                })
+                .await
+            {
+                tracing::error!("Cannot respond to autocomplete: {}", why);
+            }
+
+            return;
+        }
+
+        if let Interaction::MessageComponent(component) = &interaction {
+            debug!("Received component interaction: {:#?}", component);
+
+            if component.guild_id.is_none() {
+                return;
+            }
+
+            let language = Language::select(&component.locale, component.guild_locale.as_deref());
+
+            // The second part of every custom id is the user the component belongs to.
+            let owner_id = component.data.custom_id.split(':').nth(1);
+
+            if owner_id != Some(component.user.id.to_string().as_str()) {
+                if let Err(why) = component
+                    .create_interaction_response(&ctx.http, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|message| {
+                                message
+                                    .content(language.tr("failure.not-owner"))
+                                    .ephemeral(true)
+                            })
+                    })
+                    .await
+                {
+                    tracing::error!("Cannot respond to component: {}", why);
+                }
+
+                return;
+            }
+
+            // Confirming an import can take longer than Discord waits for a response.
+            if let Err(why) = component.defer(&ctx.http).await {
+                tracing::error!("Cannot defer component response: {}", why);
+                return;
+            }
+
+            let content = dispatch_component(component, &ctx, &self.database, language).await;
+            let response = unwrap_command_response(content, language);
+
+            if let Err(why) = component
+                .edit_original_interaction_response(&ctx.http, |message| {
+                    message.set_embed(response.embed).components(|components| {
+                        *components = response.components;
+                        components
+                    })
+                })
+                .await
+            {
+                tracing::error!("Cannot respond to component: {}", why);
+            }
+
+            return;
+        }
+
         if let Interaction::ApplicationCommand(command) = interaction {
             debug!("Received command interaction: {:#?}", command);
 
@@ -61,30 +171,45 @@ impl EventHandler for Handler {
                 return;
             }
 
+            let language = Language::select(&command.locale, command.guild_locale.as_deref());
+
+            // Commands like the export resolve many names, which can take longer than Discord
+            // waits for a response.
+            if let Err(why) = command.defer(&ctx.http).await {
+                tracing::error!("Cannot defer slash command response: {}", why);
+                return;
+            }
+
             let content = match command.data.name.as_str() {
-                "birthday" => dispatch_birthday_sub_command(&command, &ctx, &self.database).await,
+                "birthday" => {
+                    dispatch_birthday_sub_command(
+                        &command,
+                        &ctx,
+                        &self.database,
+                        self.calendar_url.as_deref(),
+                        language,
+                    )
+                    .await
+                }
+                "birthday-admin" => {
+                    dispatch_birthday_admin_sub_command(&command, &ctx, &self.database, language)
+                        .await
+                }
                 _ => Ok(CreateEmbed(HashMap::new())
-                    .title("Interaction failure")
-                    .description("Command has not been implemented.")
-                    .to_owned()),
+                    .title(language.tr("title.failure"))
+                    .description(language.tr("failure.not-implemented"))
+                    .to_owned()
+                    .into()),
             };
 
-            let embed = match content {
-                Ok(e) => e,
-                Err(why) => {
-                    tracing::error!("Cannot respond to slash command: {:?}", why);
-                    CreateEmbed(HashMap::new())
-                        .title("Interaction failure")
-                        .description("Command ran into an error.")
-                        .to_owned()
-                }
-            };
+            let response = unwrap_command_response(content, language);
 
             if let Err(why) = command
-                .create_interaction_response(&ctx.http, |response| {
-                    response
-                        .kind(InteractionResponseType::ChannelMessageWithSource)
-                        .interaction_response_data(|message| message.add_embed(embed))
+                .edit_original_interaction_response(&ctx.http, |message| {
+                    message.add_embed(response.embed).components(|components| {
+                        *components = response.components;
+                        components
+                    })
                 })
                 .await
             {
@@ -106,14 +231,31 @@ impl EventHandler for Handler {
             guild_command
         );
 
+        let guild_command = Command::create_global_application_command(&ctx.http, |command| {
+            commands::birthday_admin::register(command)
+        })
+        .await;
+
+        debug!(
+            "I created the following global slash command: {:#?}",
+            guild_command
+        );
+
         let ctx = Arc::new(ctx);
         let db = Arc::new(self.database.clone());
 
         if !self.is_loop_running.load(Ordering::Relaxed) {
             let ctx3 = Arc::clone(&ctx);
             let db1 = Arc::clone(&db);
+            let catch_up_days = self.catch_up_days;
 
             tokio::spawn(async move {
+                if let Err(why) =
+                    catch_up_birthdays(Arc::clone(&ctx3), Arc::clone(&db1), catch_up_days).await
+                {
+                    error!("Failed to catch up on birthdays, err: {}", why);
+                };
+
                 loop {
                     if let Err(why) =
                         notify_birthdays(Arc::clone(&ctx3), Arc::clone(&db1)).await
@@ -140,48 +282,332 @@ async fn notify_birthdays(
 ) -> Result<(), sqlx::Error> {
     info!("Notification started!");
 
-    let today = Utc::now().naive_utc().date();
+    let now = Utc::now();
+    let utc_today = now.naive_utc().date();
+
+    // Reminders look ahead up to the maximum reminder offset.
+    let birthdays = get_birthdays_between(
+        &db,
+        utc_today,
+        utc_today + ChronoDuration::days(MAX_REMINDER_DAYS as i64),
+    )
+    .await?;
 
-    let birthdays = Birthday::get_all(&db).await?;
-    let birthdays = birthdays
-        .iter()
-        .filter(|b| b.date.date() == today);
+    let mut guild_settings: HashMap<u64, GuildSettings> = HashMap::new();
+
+    let user_ids: Vec<u64> = birthdays.iter().map(|b| b.user_id()).collect();
+    let timezones = UserSettings::get_timezones(&db, &user_ids).await?;
 
     for birthday in birthdays {
-        if let Ok(bday_user) = ctx.http.get_user(birthday.user_id()).await {
-            let subscriptions =
-                Subscription::get_all_by_birthday_id(&db, birthday.id_birthday, today.year()).await?;
+        let settings = match guild_settings.get(&birthday.guild_id()) {
+            Some(settings) => settings.clone(),
+            None => {
+                let settings =
+                    GuildSettings::get_or_default(&db, birthday.guild_id(), now.naive_utc())
+                        .await?;
+                guild_settings.insert(birthday.guild_id(), settings.clone());
+                settings
+            }
+        };
+
+        let celebrant_tz = timezones.get(&birthday.user_id()).copied().unwrap_or(Tz::UTC);
+        let today = now.with_timezone(&celebrant_tz).date_naive();
+        let occurrence =
+            recurrence::next_occurrence(birthday.date.date(), today, settings.leap_day_policy());
+        let days_before = (occurrence - today).num_days() as i32;
+
+        if days_before > MAX_REMINDER_DAYS {
+            continue;
+        }
+
+        if let (0, Some(channel_id)) = (days_before, settings.announcement_channel_id()) {
+            if let Err(why) = send_birthday_announcement(
+                &ctx, &db, &birthday, &settings, occurrence, channel_id, false,
+            )
+            .await
+            {
+                error!("Failed to announce birthday, err: {}", why);
+            }
+        }
 
-            send_birthday_dm(subscriptions, &ctx, &db, &bday_user.name).await;
+        if let (0, Some(role_id)) = (days_before, settings.birthday_role_id()) {
+            if let Err(why) =
+                assign_birthday_role(&ctx, &db, &birthday, celebrant_tz, occurrence, role_id).await
+            {
+                error!("Failed to assign birthday role, err: {}", why);
+            }
+        }
+
+        if birthday.visibility() == Visibility::AnnouncementsOnly {
+            continue;
+        }
+
+        let subscriptions = Subscription::get_all_by_birthday_id(
+            &db,
+            birthday.id_birthday,
+            occurrence.year(),
+            days_before,
+        )
+        .await?;
+
+        if subscriptions.is_empty() {
+            continue;
+        }
+
+        if let Ok(bday_user) = ctx.http.get_user(birthday.user_id()).await {
+            send_birthday_dm(
+                subscriptions,
+                &ctx,
+                &db,
+                &bday_user.name,
+                &birthday,
+                celebrant_tz,
+                &settings,
+                today,
+                occurrence,
+            )
+            .await;
         }
         else {
             warn!("Could not find user: {}", birthday.user_id());
         }
     }
 
+    remove_birthday_roles(&ctx, &db).await?;
+
+    SchedulerState::new(now.naive_utc()).save(&db).await?;
+
     info!("Notification finished!");
 
     Ok(())
 }
 
+async fn catch_up_birthdays(
+    ctx: Arc<Context>,
+    db: Arc<PgPool>,
+    catch_up_days: u32,
+) -> Result<(), sqlx::Error> {
+    let last_run_date = match SchedulerState::get(&db).await? {
+        Some(state) => state.last_run_date,
+        None => return Ok(()),
+    };
+
+    let now = Utc::now();
+    let window_start = last_run_date.max(now.naive_utc() - ChronoDuration::days(catch_up_days.into()));
+
+    info!("Catching up on birthdays since {}!", window_start);
+
+    // Reminders missed in the window look ahead up to the maximum reminder offset.
+    let birthdays = get_birthdays_between(
+        &db,
+        window_start.date(),
+        now.naive_utc().date() + ChronoDuration::days(MAX_REMINDER_DAYS as i64),
+    )
+    .await?;
+
+    let user_ids: Vec<u64> = birthdays.iter().map(|b| b.user_id()).collect();
+    let timezones = UserSettings::get_timezones(&db, &user_ids).await?;
+
+    for birthday in birthdays {
+        let settings =
+            GuildSettings::get_or_default(&db, birthday.guild_id(), now.naive_utc()).await?;
+
+        let timezone = timezones.get(&birthday.user_id()).copied().unwrap_or(Tz::UTC);
+        let first_day = Utc
+            .from_utc_datetime(&window_start)
+            .with_timezone(&timezone)
+            .date_naive();
+        let today = now.with_timezone(&timezone).date_naive();
+
+        // Today is handled by the regular notification loop.
+        for date in first_day.iter_days().take_while(|d| *d < today) {
+            let occurrence =
+                recurrence::next_occurrence(birthday.date.date(), date, settings.leap_day_policy());
+            let days_before = (occurrence - date).num_days() as i32;
+
+            if days_before > MAX_REMINDER_DAYS {
+                continue;
+            }
+
+            if let (0, Some(channel_id)) = (days_before, settings.announcement_channel_id()) {
+                if let Err(why) = send_birthday_announcement(
+                    &ctx, &db, &birthday, &settings, occurrence, channel_id, true,
+                )
+                .await
+                {
+                    error!("Failed to announce birthday, err: {}", why);
+                }
+            }
+
+            // The role marks the day itself, so it is not assigned once the day is over.
+            if let (0, Some(_)) = (days_before, settings.birthday_role_id()) {
+                info!(
+                    "Skipped birthday role of birthday {} on {}, the day is over",
+                    birthday.id_birthday, date
+                );
+            }
+
+            if birthday.visibility() == Visibility::AnnouncementsOnly {
+                continue;
+            }
+
+            let subscriptions = Subscription::get_all_by_birthday_id(
+                &db,
+                birthday.id_birthday,
+                occurrence.year(),
+                days_before,
+            )
+            .await?;
+
+            if subscriptions.is_empty() {
+                continue;
+            }
+
+            // Reminders for a birthday which has passed since are replaced by the belated message.
+            if days_before > 0 && occurrence < today {
+                info!(
+                    "Skipped {} reminders of birthday {} {} days before {}, the birthday is over",
+                    subscriptions.len(),
+                    birthday.id_birthday,
+                    days_before,
+                    occurrence
+                );
+                continue;
+            }
+
+            if let Ok(bday_user) = ctx.http.get_user(birthday.user_id()).await {
+                send_birthday_dm(
+                    subscriptions,
+                    &ctx,
+                    &db,
+                    &bday_user.name,
+                    &birthday,
+                    timezone,
+                    &settings,
+                    date,
+                    occurrence,
+                )
+                .await;
+            } else {
+                warn!("Could not find user: {}", birthday.user_id());
+            }
+        }
+    }
+
+    info!("Catch up finished!");
+
+    Ok(())
+}
+
+/// Loads all birthdays which might be celebrated between the two utc days in any timezone.
+async fn get_birthdays_between(
+    db: &PgPool,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+) -> Result<Vec<Birthday>, sqlx::Error> {
+    // The local day of every timezone is at most one day apart from the utc day.
+    let first_day = first_day - ChronoDuration::days(1);
+    let last_day = last_day + ChronoDuration::days(1);
+
+    let mut days: Vec<(u32, u32)> = Vec::new();
+
+    for local_day in first_day.iter_days().take_while(|d| *d <= last_day) {
+        for month_and_day in recurrence::candidate_days(local_day) {
+            if !days.contains(&month_and_day) {
+                days.push(month_and_day);
+            }
+        }
+    }
+
+    Birthday::get_all_by_month_and_days(db, &days).await
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn send_birthday_dm(
     subscriptions: Vec<Subscription>,
     ctx: &Arc<Context>,
     db: &Arc<PgPool>,
     user_name: &str,
+    birthday: &Birthday,
+    celebrant_tz: Tz,
+    settings: &GuildSettings,
+    date: NaiveDate,
+    occurrence: NaiveDate,
 ) {
-    let today = Utc::now().naive_utc();
+    let now = Utc::now();
+    let today = now.naive_utc();
+    let local_today = now.with_timezone(&celebrant_tz).date_naive();
+    let days_before = (occurrence - date).num_days() as i32;
+    // Reminders sent late tell how many days are actually left.
+    let days_left = (occurrence - local_today).num_days() as i32;
+    let is_belated = occurrence < local_today;
+
+    let subscriber_ids: Vec<u64> = subscriptions.iter().map(|s| s.user_id()).collect();
+    let subscriber_timezones = match UserSettings::get_timezones(db, &subscriber_ids).await {
+        Ok(timezones) => timezones,
+        Err(why) => {
+            error!("Could not load timezones of subscribers, why: {why}");
+            return;
+        }
+    };
+
+    let age = birthday.shared_age_on(occurrence);
+    let is_milestone = age.is_some_and(|age| settings.is_milestone(age));
+    let language = utils::get_guild_language(ctx, GuildId(birthday.guild_id()));
+
+    // Belated notifications always use the built-in text, as templates are worded for the day itself.
+    let custom_text = match (&settings.dm_template, is_belated) {
+        (Some(dm_template), false) => {
+            let values = TemplateValues {
+                mention: format!("<@{}>", birthday.user_id()),
+                name: user_name.to_string(),
+                age,
+                date: occurrence,
+                when: gen_days_before_text(days_left, language),
+                guild: utils::get_guild_name(ctx, GuildId(birthday.guild_id())).await,
+            };
+            template::render(dm_template, &values)
+        }
+        _ => None,
+    };
+
+    let mut text = custom_text.unwrap_or_else(|| {
+        gen_reminder_text(user_name, days_left, occurrence, is_belated, age, language)
+    });
+
+    if is_milestone {
+        text.push(' ');
+        text.push_str(&language.tr("reminder.milestone"));
+    }
 
     for subscription in subscriptions {
+        let subscriber_tz = subscriber_timezones
+            .get(&subscription.user_id())
+            .copied()
+            .unwrap_or(Tz::UTC);
+
+        if !recurrence::is_delivery_due(
+            now,
+            date,
+            celebrant_tz,
+            subscriber_tz,
+            subscription.notify_time,
+        ) {
+            continue;
+        }
+
         if let Ok(user) = ctx.http.get_user(subscription.user_id()).await {
             if let Ok(priv_channel) = user.create_dm_channel(ctx).await {
                 if let Err(why) = priv_channel
                     .send_message(&ctx.http, |message| {
                         message.embed(|embed| {
-                            embed.title("Birthday:").description(format!(
-                                "Hey the user `{}` has birthday today ({}).",
-                                user_name, today,
-                            ))
+                            embed.title(language.tr("title.birthday")).description(&text);
+
+                            if is_milestone {
+                                embed.colour(Colour::GOLD);
+                            }
+
+                            embed
                         })
                     })
                     .await
@@ -189,9 +615,14 @@ async fn send_birthday_dm(
                     error!("Could not send birthday in dm channel, err: {}", why);
                 } else {
                     
-                    let mut send_notification = SendNotification::new(subscription.id_subscription, today.year(), today);
+                    let mut send_notification = SendNotification::new(
+                        subscription.id_subscription,
+                        occurrence.year(),
+                        days_before,
+                        today,
+                    );
                     
-                    match send_notification.insert(&db).await {
+                    match send_notification.insert(db).await {
                         Ok(_) => info!("Notified of birthday!"),
                         Err(why) => error!("Could not create notifcation, why: {why}"),
                     };
@@ -201,56 +632,587 @@ async fn send_birthday_dm(
     }
 }
 
+async fn send_birthday_announcement(
+    ctx: &Arc<Context>,
+    db: &Arc<PgPool>,
+    birthday: &Birthday,
+    settings: &GuildSettings,
+    occurrence: NaiveDate,
+    channel_id: u64,
+    is_belated: bool,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let language = utils::get_guild_language(ctx, GuildId(birthday.guild_id()));
+
+    let mut announcement =
+        match SendAnnouncement::get(db, birthday.id_birthday, occurrence.year()).await? {
+            None => SendAnnouncement::new(birthday.id_birthday, occurrence.year(), now),
+            Some(announcement) if announcement.is_retry_due(now) => announcement,
+            Some(_) => return Ok(()),
+        };
+
+    let values = TemplateValues {
+        mention: format!("<@{}>", birthday.user_id()),
+        name: match ctx.http.get_user(birthday.user_id()).await {
+            Ok(user) => user.name,
+            Err(_) => format!("<@{}>", birthday.user_id()),
+        },
+        age: birthday.shared_age_on(occurrence),
+        date: occurrence,
+        when: language.tr("days.today"),
+        guild: utils::get_guild_name(ctx, GuildId(birthday.guild_id())).await,
+    };
+
+    // Belated announcements always use the built-in text, as templates are worded for the day itself.
+    let text = if is_belated {
+        language.tr_args(
+            "announcement.belated",
+            &[("mention", values.mention), ("date", occurrence.to_string())],
+        )
+    } else {
+        settings
+            .announcement_template
+            .as_deref()
+            .and_then(|announcement_template| template::render(announcement_template, &values))
+            .or_else(|| template::render(DEFAULT_ANNOUNCEMENT_TEMPLATE, &values))
+            .unwrap_or_default()
+    };
+
+    // The attempt is stored first, so the announcement is never sent twice if storing fails.
+    announcement.record_attempt();
+    announcement.save(db).await?;
+
+    if let Err(why) = ChannelId(channel_id)
+        .send_message(&ctx.http, |message| message.content(&text))
+        .await
+    {
+        error!(
+            "Could not send birthday announcement, attempt {}, err: {}",
+            announcement.attempts, why
+        );
+        announcement.record_failure(Utc::now().naive_utc());
+        announcement.save(db).await?;
+
+        return Ok(());
+    }
+
+    info!("Announced birthday!");
+
+    Ok(())
+}
+
+async fn assign_birthday_role(
+    ctx: &Arc<Context>,
+    db: &Arc<PgPool>,
+    birthday: &Birthday,
+    celebrant_tz: Tz,
+    occurrence: NaiveDate,
+    role_id: u64,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    let mut role_assignment =
+        match RoleAssignment::get(db, birthday.id_birthday, occurrence.year()).await? {
+            None => {
+                let remove_date = match occurrence.succ_opt() {
+                    Some(next_day) => recurrence::day_start(next_day, celebrant_tz).naive_utc(),
+                    None => now,
+                };
+
+                RoleAssignment::new(
+                    birthday.id_birthday,
+                    birthday.guild_id(),
+                    birthday.user_id(),
+                    role_id,
+                    occurrence.year(),
+                    remove_date,
+                    now,
+                )
+            }
+            Some(role_assignment) if role_assignment.is_retry_due(now) => role_assignment,
+            Some(_) => return Ok(()),
+        };
+
+    // The attempt is stored before the role is assigned, so a role is never left without a removal.
+    role_assignment.record_attempt();
+    role_assignment.save(db).await?;
+
+    if let Err(why) = ctx
+        .http
+        .add_member_role(
+            birthday.guild_id(),
+            birthday.user_id(),
+            role_id,
+            Some("Birthday started"),
+        )
+        .await
+    {
+        error!(
+            "Could not assign birthday role, attempt {}, err: {}",
+            role_assignment.attempts, why
+        );
+        role_assignment.record_failure(Utc::now().naive_utc());
+        role_assignment.save(db).await?;
+
+        return Ok(());
+    }
+
+    info!("Assigned birthday role!");
+
+    Ok(())
+}
+
+async fn remove_birthday_roles(ctx: &Arc<Context>, db: &Arc<PgPool>) -> Result<(), sqlx::Error> {
+    let role_assignments = RoleAssignment::get_all_expired(db, Utc::now().naive_utc()).await?;
+
+    for mut role_assignment in role_assignments {
+        match ctx
+            .http
+            .remove_member_role(
+                role_assignment.guild_id(),
+                role_assignment.user_id(),
+                role_assignment.role_id(),
+                Some("Birthday ended"),
+            )
+            .await
+        {
+            Ok(_) => info!("Removed birthday role!"),
+            Err(SerenityError::Http(why)) if is_not_found(&why) => {
+                warn!("Member or role of birthday role assignment no longer exists.")
+            }
+            Err(why) => {
+                error!("Could not remove birthday role, err: {}", why);
+                continue;
+            }
+        }
+
+        role_assignment.removed_date = Some(Utc::now().naive_utc());
+
+        if let Err(why) = role_assignment.update(db).await {
+            error!("Could not store removed birthday role, err: {}", why);
+        }
+    }
+
+    Ok(())
+}
+
+fn is_not_found(why: &HttpError) -> bool {
+    match why {
+        HttpError::UnsuccessfulRequest(response) => response.status_code.as_u16() == 404,
+        _ => false,
+    }
+}
+
+fn gen_reminder_text(
+    user_name: &str,
+    days_before: i32,
+    occurrence: NaiveDate,
+    is_belated: bool,
+    age: Option<i32>,
+    language: Language,
+) -> String {
+    let mut args = vec![
+        ("name", user_name.to_string()),
+        ("date", occurrence.to_string()),
+        ("when", gen_days_before_text(days_before, language)),
+    ];
+
+    let key = match (age, is_belated) {
+        (Some(_), true) => "reminder.belated-age",
+        (None, true) => "reminder.belated",
+        (Some(_), false) => "reminder.age",
+        (None, false) => "reminder.plain",
+    };
+
+    if let Some(age) = age {
+        args.push(("age", age.to_string()));
+    }
+
+    language.tr_args(key, &args)
+}
+
+fn gen_days_before_text(days_before: i32, language: Language) -> String {
+    match days_before {
+        0 => language.tr("days.today"),
+        1 => language.tr("days.tomorrow"),
+        _ => language.tr_args("days.in", &[("days", days_before.to_string())]),
+    }
+}
+
 async fn dispatch_birthday_sub_command(
     command: &ApplicationCommandInteraction,
     ctx: &Context,
     database: &sqlx::PgPool,
-) -> Result<CreateEmbed, CommandError> {
+    calendar_url: Option<&str>,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
     let embed = CreateEmbed(HashMap::new())
-        .title("Interaction failure")
-        .description("Command has not been implemented.")
+        .title(language.tr("title.failure"))
+        .description(language.tr("failure.not-implemented"))
         .to_owned();
 
-    if let Some(subcommand) = command.data.options.get(0) {
+    if let Some(subcommand) = command.data.options.first() {
         return match subcommand.name.as_str() {
             "info" => {
-                run_info_command(&database, &ctx, &command.guild_id.unwrap(), &command.user).await
+                run_info_command(
+                    database,
+                    ctx,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
             }
             "set" => {
                 run_set_command(
-                    &database,
+                    database,
                     &command.guild_id.unwrap(),
                     &command.user,
                     &subcommand.options,
+                    language,
                 )
                 .await
+                .map(CommandResponse::from)
             }
             "remove" => {
-                run_remove_command(&database, &command.guild_id.unwrap(), &command.user).await
+                run_remove_command(database, &command.guild_id.unwrap(), &command.user, language)
+                    .await
+                    .map(CommandResponse::from)
             }
             "subscribe" => {
                 run_subscribe_command(
-                    &database,
+                    database,
                     &command.guild_id.unwrap(),
                     &command.user,
                     &subcommand.options,
+                    language,
                 )
                 .await
+                .map(CommandResponse::from)
             }
             "unsubscribe" => {
                 run_unsubscribe_command(
-                    &database,
+                    database,
                     &command.guild_id.unwrap(),
                     &command.user,
                     &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "timezone" => {
+                run_timezone_command(
+                    database,
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "upcoming" => {
+                run_upcoming_command(
+                    database,
+                    ctx,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "clear-all" => run_clear_all_command(&command.user, language),
+            "export" => run_export_command(database, ctx, &command.user, language)
+                .await
+                .map(CommandResponse::from),
+            "privacy" => run_privacy_command(
+                database,
+                &command.guild_id.unwrap(),
+                &command.user,
+                &subcommand.options,
+                language,
+            )
+            .await
+            .map(CommandResponse::from),
+            "calendar" => {
+                run_calendar_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+            }
+            "calendar-link" => {
+                run_calendar_link_command(
+                    database,
+                    ctx,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    calendar_url,
+                    language,
                 )
                 .await
+                .map(CommandResponse::from)
             }
-            _ => Ok(embed),
+            "subscriptions" => {
+                run_subscriptions_command(
+                    database,
+                    ctx,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    0,
+                    language,
+                )
+                .await
+            }
+            _ => Ok(embed.into()),
         };
     }
 
-    Ok(embed)
+    Ok(embed.into())
+}
+
+async fn dispatch_birthday_admin_sub_command(
+    command: &ApplicationCommandInteraction,
+    ctx: &Context,
+    database: &sqlx::PgPool,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let embed = CreateEmbed(HashMap::new())
+        .title(language.tr("title.failure"))
+        .description(language.tr("failure.not-implemented"))
+        .to_owned();
+
+    if let Some(subcommand) = command.data.options.first() {
+        return match subcommand.name.as_str() {
+            "channel" => {
+                run_channel_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "role" => {
+                run_role_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "milestones" => {
+                run_milestones_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "template" => {
+                run_template_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "template-preview" => {
+                run_template_preview_command(
+                    database,
+                    ctx,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "set" => {
+                run_set_member_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "remove" => {
+                run_remove_member_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "export" => {
+                run_export_guild_command(
+                    database,
+                    ctx,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            "import" => {
+                run_import_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+            }
+            "audit" => {
+                run_audit_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+            }
+            "leap-day" => {
+                run_leap_day_command(
+                    database,
+                    &command.guild_id.unwrap(),
+                    &command.user,
+                    &subcommand.options,
+                    language,
+                )
+                .await
+                .map(CommandResponse::from)
+            }
+            _ => Ok(embed.into()),
+        };
+    }
+
+    Ok(embed.into())
+}
+
+async fn dispatch_component(
+    component: &MessageComponentInteraction,
+    ctx: &Context,
+    database: &sqlx::PgPool,
+    language: Language,
+) -> Result<CommandResponse, CommandError> {
+    let parts: Vec<&str> = component.data.custom_id.split(':').collect();
+
+    match parts.as_slice() {
+        ["subscriptions", _, page] => {
+            run_subscriptions_command(
+                database,
+                ctx,
+                &component.guild_id.unwrap(),
+                &component.user,
+                page.parse().unwrap_or_default(),
+                language,
+            )
+            .await
+        }
+        ["calendar", _, year, month] => {
+            run_calendar_page(
+                database,
+                &component.guild_id.unwrap(),
+                &component.user,
+                year.parse().unwrap_or_default(),
+                month.parse().unwrap_or_default(),
+                language,
+            )
+            .await
+        }
+        ["audit", _, target_id, page] => {
+            run_audit_page(
+                database,
+                &component.guild_id.unwrap(),
+                &component.user,
+                target_id.parse().ok().filter(|id| *id != 0),
+                page.parse().unwrap_or_default(),
+                language,
+            )
+            .await
+        }
+        ["import", _, import_id, action] => {
+            run_import_confirmation(
+                database,
+                &component.guild_id.unwrap(),
+                &component.user,
+                import_id.parse().unwrap_or_default(),
+                *action == "confirm",
+                language,
+            )
+            .await
+        }
+        ["subscribe", _, target_id] => {
+            run_subscribe_button(
+                database,
+                &component.guild_id.unwrap(),
+                &component.user,
+                target_id.parse().unwrap_or_default(),
+                language,
+            )
+            .await
+        }
+        ["clear-all", _, action] => {
+            run_clear_all_confirmation(database, &component.user, *action == "confirm", language)
+                .await
+        }
+        _ => Ok(CreateEmbed(HashMap::new())
+            .title(language.tr("title.failure"))
+            .description(language.tr("failure.component-not-implemented"))
+            .to_owned()
+            .into()),
+    }
+}
+
+fn unwrap_command_response(
+    content: Result<CommandResponse, CommandError>,
+    language: Language,
+) -> CommandResponse {
+    match content {
+        Ok(response) => response,
+        Err(CommandError::AlreadyExists) => CreateEmbed(HashMap::new())
+            .title(language.tr("title.failure"))
+            .description(language.tr("failure.already-exists"))
+            .to_owned()
+            .into(),
+        Err(why) => {
+            tracing::error!("Cannot respond to interaction: {:?}", why);
+            CreateEmbed(HashMap::new())
+                .title(language.tr("title.failure"))
+                .description(language.tr("failure.error"))
+                .to_owned()
+                .into()
+        }
+    }
 }
 
 #[instrument]
@@ -0,0 +1,119 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommandOption};
+
+/// The languages with a translation catalog in `locales/`.
+///
+/// English is the default language, its texts are also used for keys missing in other catalogs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// Maps a Discord locale like `de` or `en-US` to a language.
+    pub fn from_locale(locale: &str) -> Option<Language> {
+        match locale.split('-').next() {
+            Some("en") => Some(Language::English),
+            Some("de") => Some(Language::German),
+            _ => None,
+        }
+    }
+
+    /// Picks the language of the user, falling back to the language of the guild.
+    pub fn select(user_locale: &str, guild_locale: Option<&str>) -> Language {
+        Language::from_locale(user_locale)
+            .or_else(|| guild_locale.and_then(Language::from_locale))
+            .unwrap_or_default()
+    }
+
+    /// The Discord locale under which localized command names are registered.
+    pub fn discord_locale(&self) -> &'static str {
+        match self {
+            Language::English => "en-US",
+            Language::German => "de",
+        }
+    }
+
+    pub fn tr(&self, key: &str) -> String {
+        self.tr_args(key, &[])
+    }
+
+    /// Looks up the text of a key and replaces its `{name}` placeholders.
+    pub fn tr_args(&self, key: &str, args: &[(&str, String)]) -> String {
+        let mut text = self
+            .get(key)
+            .or_else(|| Language::English.get(key))
+            .unwrap_or(key)
+            .to_string();
+
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+
+        text
+    }
+
+    fn get(&self, key: &str) -> Option<&'static str> {
+        self.catalog().get(key).map(|text| text.as_str())
+    }
+
+    fn catalog(&self) -> &'static HashMap<String, String> {
+        static ENGLISH: OnceLock<HashMap<String, String>> = OnceLock::new();
+        static GERMAN: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+        match self {
+            Language::English => ENGLISH.get_or_init(|| parse(include_str!("../locales/en.json"))),
+            Language::German => GERMAN.get_or_init(|| parse(include_str!("../locales/de.json"))),
+        }
+    }
+}
+
+/// Returns the translations of a key in every language besides English.
+pub fn localizations(key: &str) -> Vec<(&'static str, &'static str)> {
+    Language::ALL
+        .iter()
+        .filter(|language| **language != Language::English)
+        .filter_map(|language| Some((language.discord_locale(), language.get(key)?)))
+        .collect()
+}
+
+/// Registers the translated names and descriptions of commands and options.
+pub trait Localize {
+    /// Adds the translations of `<key>.name` and `<key>.description`.
+    fn localize(&mut self, key: &str) -> &mut Self;
+}
+
+impl Localize for CreateApplicationCommand {
+    fn localize(&mut self, key: &str) -> &mut Self {
+        for (locale, name) in localizations(&format!("{}.name", key)) {
+            self.name_localized(locale, name);
+        }
+        for (locale, description) in localizations(&format!("{}.description", key)) {
+            self.description_localized(locale, description);
+        }
+
+        self
+    }
+}
+
+impl Localize for CreateApplicationCommandOption {
+    fn localize(&mut self, key: &str) -> &mut Self {
+        for (locale, name) in localizations(&format!("{}.name", key)) {
+            self.name_localized(locale, name);
+        }
+        for (locale, description) in localizations(&format!("{}.description", key)) {
+            self.description_localized(locale, description);
+        }
+
+        self
+    }
+}
+
+fn parse(source: &str) -> HashMap<String, String> {
+    serde_json::from_str(source).expect("Translation catalog should be valid json.")
+}
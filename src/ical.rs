@@ -0,0 +1,171 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+
+use crate::models::birthday::Birthday;
+use crate::recurrence::LeapDayPolicy;
+
+/// The maximum length of a line in octets, longer lines are folded.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// A birthday as an all-day event which recurs every year.
+pub struct BirthdayEvent {
+    pub uid: String,
+    pub summary: String,
+    pub date: NaiveDate,
+}
+
+impl BirthdayEvent {
+    /// Creates the event of a birthday as seen by other users, without a hidden birth year.
+    pub fn from_birthday(birthday: &Birthday, name: &str) -> BirthdayEvent {
+        BirthdayEvent {
+            uid: format!("birthday-{}@birthdaybot", birthday.id_birthday),
+            summary: format!("Birthday of {}", name),
+            date: birthday.shared_date(),
+        }
+    }
+}
+
+/// Generates an iCalendar file with the events, `stamp` is the time of creation in utc.
+pub fn gen_calendar(
+    name: &str,
+    events: &[BirthdayEvent],
+    policy: LeapDayPolicy,
+    stamp: NaiveDateTime,
+) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//birthdaybot//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    for event in events {
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!("UID:{}", escape_text(&event.uid)));
+        lines.push(format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")));
+        lines.push(format!(
+            "DTSTART;VALUE=DATE:{}",
+            event.date.format("%Y%m%d")
+        ));
+        lines.push(format!("RRULE:{}", gen_rule(event.date, policy)));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        lines.push(String::from("TRANSP:TRANSPARENT"));
+        lines.push(String::from("END:VEVENT"));
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+
+    let lines: Vec<String> = lines.iter().map(|line| fold_line(line)).collect();
+    lines.join("\r\n") + "\r\n"
+}
+
+/// Describes the yearly recurrence, birthdays on the 29th of February follow the leap day policy.
+fn gen_rule(date: NaiveDate, policy: LeapDayPolicy) -> &'static str {
+    if date.month() != 2 || date.day() != 29 {
+        return "FREQ=YEARLY";
+    }
+
+    match policy {
+        LeapDayPolicy::February28 => "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1",
+        // Day 60 is the 29th of February in leap years and the 1st of March otherwise.
+        LeapDayPolicy::March1 => "FREQ=YEARLY;BYYEARDAY=60",
+        // Recurrences on days which do not exist are skipped.
+        LeapDayPolicy::LeapYearsOnly => "FREQ=YEARLY",
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Splits a line into lines of at most 75 octets, continued lines start with a space.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn rule_of_ordinary_days_recurs_yearly() {
+        assert_eq!(gen_rule(date(2000, 3, 1), LeapDayPolicy::March1), "FREQ=YEARLY");
+        assert_eq!(gen_rule(date(2000, 2, 28), LeapDayPolicy::February28), "FREQ=YEARLY");
+    }
+
+    #[test]
+    fn rule_of_leap_days_follows_the_policy() {
+        let leap_day = date(2000, 2, 29);
+
+        assert_eq!(
+            gen_rule(leap_day, LeapDayPolicy::February28),
+            "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1"
+        );
+        assert_eq!(gen_rule(leap_day, LeapDayPolicy::March1), "FREQ=YEARLY;BYYEARDAY=60");
+        assert_eq!(gen_rule(leap_day, LeapDayPolicy::LeapYearsOnly), "FREQ=YEARLY");
+    }
+
+    #[test]
+    fn escape_text_escapes_separators_and_newlines() {
+        assert_eq!(escape_text("a,b;c\\d\ne"), r"a\,b\;c\\d\ne");
+    }
+
+    #[test]
+    fn short_lines_are_not_folded() {
+        let line = "a".repeat(MAX_LINE_LENGTH);
+
+        assert_eq!(fold_line(&line), line);
+    }
+
+    #[test]
+    fn fold_line_does_not_split_multibyte_characters() {
+        // The two octets of "ä" would end at octet 76, so the line is folded before it.
+        let line = format!("{}ä{}", "a".repeat(74), "ö".repeat(40));
+        let folded = fold_line(&line);
+        let parts: Vec<&str> = folded.split("\r\n").collect();
+
+        assert_eq!(parts[0], "a".repeat(74));
+        assert!(parts[1].starts_with(" ä"));
+        assert!(parts.iter().all(|part| part.len() <= MAX_LINE_LENGTH));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn calendar_lines_end_with_crlf() {
+        let events = [BirthdayEvent {
+            uid: String::from("birthday-1@birthdaybot"),
+            summary: String::from("Birthday of Jane, Doe"),
+            date: date(2000, 2, 29),
+        }];
+        let stamp = date(2024, 1, 2).and_hms_opt(3, 4, 5).unwrap();
+
+        let calendar = gen_calendar("Server", &events, LeapDayPolicy::March1, stamp);
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(calendar.contains("\r\nDTSTART;VALUE=DATE:20000229\r\n"));
+        assert!(calendar.contains("\r\nRRULE:FREQ=YEARLY;BYYEARDAY=60\r\n"));
+        assert!(calendar.contains("\r\nSUMMARY:Birthday of Jane\\, Doe\r\n"));
+        assert!(calendar.contains("\r\nDTSTAMP:20240102T030405Z\r\n"));
+    }
+}
@@ -1,14 +1,27 @@
-use std::{env, sync::atomic::AtomicBool};
+use std::{
+    env,
+    sync::{atomic::AtomicBool, Arc},
+};
 
 use handler::Handler;
 use serenity::{prelude::GatewayIntents, Client};
-use tracing::{error, instrument};
+use tokio::net::TcpListener;
+use tracing::{error, info, instrument};
 
 mod commands;
 mod handler;
+mod i18n;
+mod ical;
 mod models;
+mod recurrence;
+mod retry;
+mod server;
+mod template;
 pub mod utils;
 
+/// The most days of missed notifications which are sent belated.
+const MAX_CATCH_UP_DAYS: u32 = 30;
+
 #[tokio::main]
 #[instrument]
 async fn main() {
@@ -16,6 +29,15 @@ async fn main() {
 
     let token = env::var("BIRTHDAY_BOT_TOKEN").expect("Expected a token in the environment");
     let db_url = env::var("BIRTHDAY_BOT_DATABASE_URL").expect("Expected database url in the environment");
+    let catch_up_days = env::var("BIRTHDAY_BOT_CATCH_UP_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<u32>().ok())
+        .unwrap_or(3)
+        .min(MAX_CATCH_UP_DAYS);
+    let calendar_address = env::var("BIRTHDAY_BOT_CALENDAR_ADDRESS").ok();
+    let calendar_url = env::var("BIRTHDAY_BOT_CALENDAR_URL")
+        .ok()
+        .filter(|_| calendar_address.is_some());
 
     let database = sqlx::postgres::PgPoolOptions::new()
         .max_connections(5)
@@ -28,15 +50,33 @@ async fn main() {
         .await
         .expect("Couldn't run database migrations");
 
+    let calendar_database = database.clone();
+
     let intents = GatewayIntents::default();
     let mut client = Client::builder(&token, intents)
         .event_handler(Handler {
             database,
             is_loop_running: AtomicBool::new(false),
+            catch_up_days,
+            calendar_url,
         })
         .await
         .expect("Err creating client");
 
+    if let Some(address) = calendar_address {
+        let http = Arc::clone(&client.cache_and_http.http);
+
+        tokio::spawn(async move {
+            match TcpListener::bind(&address).await {
+                Ok(listener) => {
+                    info!("Serving calendar feeds on {}", address);
+                    server::serve(listener, calendar_database, http).await
+                }
+                Err(why) => error!("Calendar server error: {:?}", why),
+            }
+        });
+    }
+
     if let Err(why) = client.start().await {
         error!("Client error: {:?}", why);
     }
@@ -0,0 +1,88 @@
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+
+use crate::retry;
+
+/// The announcement of a birthday in a year, recorded before it is sent.
+pub struct SendAnnouncement {
+    pub id_send_announcement: i32,
+    pub birthday_id: i32,
+    pub current_year: i32,
+    pub attempts: i32,
+    /// The earliest date of the next attempt after a failed one, empty once sent or given up.
+    pub retry_date: Option<NaiveDateTime>,
+    pub create_date: NaiveDateTime,
+}
+
+impl SendAnnouncement {
+    pub fn new(birthday_id: i32, current_year: i32, create_date: NaiveDateTime) -> Self {
+        Self {
+            id_send_announcement: 0,
+            birthday_id,
+            current_year,
+            attempts: 0,
+            retry_date: None,
+            create_date,
+        }
+    }
+
+    pub async fn get(
+        db: &PgPool,
+        birthday_id: i32,
+        current_year: i32,
+    ) -> Result<Option<SendAnnouncement>, sqlx::Error> {
+        let announcement: Option<SendAnnouncement> = sqlx::query_as!(
+            SendAnnouncement,
+            "SELECT id_send_announcement, birthday_id, current_year, attempts, retry_date, create_date
+                FROM send_announcements
+                WHERE birthday_id = $1
+                AND current_year = $2;",
+            birthday_id,
+            current_year,
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        Ok(announcement)
+    }
+
+    /// Inserts the announcement or updates the attempts of an existing one.
+    pub async fn save(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let id = sqlx::query!(
+            "INSERT INTO send_announcements
+                (birthday_id, current_year, attempts, retry_date, create_date)
+                VALUES
+                ($1, $2, $3, $4, $5)
+                ON CONFLICT (birthday_id, current_year)
+                DO UPDATE SET attempts = EXCLUDED.attempts, retry_date = EXCLUDED.retry_date
+                RETURNING id_send_announcement;",
+            self.birthday_id,
+            self.current_year,
+            self.attempts,
+            self.retry_date,
+            self.create_date,
+        )
+        .fetch_one(db)
+        .await?
+        .id_send_announcement;
+
+        self.id_send_announcement = id;
+
+        Ok(())
+    }
+
+    pub fn is_retry_due(&self, now: NaiveDateTime) -> bool {
+        self.retry_date.is_some_and(|retry_date| retry_date <= now)
+    }
+
+    /// Counts an attempt, which is treated as successful until [`SendAnnouncement::record_failure`].
+    pub fn record_attempt(&mut self) {
+        self.attempts += 1;
+        self.retry_date = None;
+    }
+
+    pub fn record_failure(&mut self, failure_date: NaiveDateTime) {
+        self.retry_date = retry::next_attempt_date(self.attempts, failure_date);
+    }
+}
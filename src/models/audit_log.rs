@@ -0,0 +1,173 @@
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{
+    types::chrono::{NaiveDateTime, Utc},
+    PgPool, Postgres, Transaction,
+};
+
+/// The kind of change recorded in the audit log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    BirthdayCreate = 0,
+    BirthdayUpdate = 1,
+    BirthdayDelete = 2,
+    SubscriptionCreate = 3,
+    SubscriptionUpdate = 4,
+    SubscriptionDelete = 5,
+    SettingsUpdate = 6,
+    DataErasure = 7,
+}
+
+impl AuditAction {
+    pub fn from_i32(value: i32) -> Option<AuditAction> {
+        match value {
+            0 => Some(AuditAction::BirthdayCreate),
+            1 => Some(AuditAction::BirthdayUpdate),
+            2 => Some(AuditAction::BirthdayDelete),
+            3 => Some(AuditAction::SubscriptionCreate),
+            4 => Some(AuditAction::SubscriptionUpdate),
+            5 => Some(AuditAction::SubscriptionDelete),
+            6 => Some(AuditAction::SettingsUpdate),
+            7 => Some(AuditAction::DataErasure),
+            _ => None,
+        }
+    }
+
+    /// The key of the action's text in the translation catalogs.
+    pub fn text_key(&self) -> &'static str {
+        match self {
+            AuditAction::BirthdayCreate => "audit.action.birthday-create",
+            AuditAction::BirthdayUpdate => "audit.action.birthday-update",
+            AuditAction::BirthdayDelete => "audit.action.birthday-delete",
+            AuditAction::SubscriptionCreate => "audit.action.subscription-create",
+            AuditAction::SubscriptionUpdate => "audit.action.subscription-update",
+            AuditAction::SubscriptionDelete => "audit.action.subscription-delete",
+            AuditAction::SettingsUpdate => "audit.action.settings-update",
+            AuditAction::DataErasure => "audit.action.data-erasure",
+        }
+    }
+}
+
+/// A change made by a user, with the state of the changed row before and after as json.
+pub struct AuditLog {
+    pub id_audit_log: i32,
+    guild_id: i64,
+    actor_id: i64,
+    target_id: Option<i64>,
+    action: i32,
+    pub before_data: Option<Value>,
+    pub after_data: Option<Value>,
+    pub create_date: NaiveDateTime,
+}
+
+impl AuditLog {
+    pub fn new(
+        guild_id: u64,
+        actor_id: u64,
+        target_id: Option<u64>,
+        action: AuditAction,
+        before_data: Option<Value>,
+        after_data: Option<Value>,
+    ) -> AuditLog {
+        AuditLog {
+            id_audit_log: 0,
+            guild_id: guild_id as i64,
+            actor_id: actor_id as i64,
+            target_id: target_id.map(|id| id as i64),
+            action: action as i32,
+            before_data,
+            after_data,
+            create_date: Utc::now().naive_utc(),
+        }
+    }
+
+    /// Returns a page of the entries of a guild, newest first, optionally only those about one user.
+    pub async fn get_page(
+        db: &PgPool,
+        guild_id: u64,
+        target_id: Option<u64>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AuditLog>, sqlx::Error> {
+        let entries: Vec<AuditLog> = sqlx::query_as!(
+            AuditLog,
+            "SELECT id_audit_log, guild_id, actor_id, target_id, action, before_data, after_data, create_date
+                FROM audit_log
+                WHERE guild_id = $1
+                AND ($2::BIGINT IS NULL OR target_id = $2)
+                ORDER BY create_date DESC, id_audit_log DESC
+                LIMIT $3
+                OFFSET $4;",
+            (guild_id as i64),
+            target_id.map(|id| id as i64),
+            limit,
+            offset,
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(entries)
+    }
+
+    pub async fn count(
+        db: &PgPool,
+        guild_id: u64,
+        target_id: Option<u64>,
+    ) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query!(
+            "SELECT COUNT(*) AS count
+                FROM audit_log
+                WHERE guild_id = $1
+                AND ($2::BIGINT IS NULL OR target_id = $2);",
+            (guild_id as i64),
+            target_id.map(|id| id as i64),
+        )
+        .fetch_one(db)
+        .await?
+        .count;
+
+        Ok(count.unwrap_or_default())
+    }
+
+    /// Inserts the entry within the transaction of the change it records.
+    pub async fn insert(&mut self, tx: &mut Transaction<'_, Postgres>) -> Result<(), sqlx::Error> {
+        let id = sqlx::query!(
+            "INSERT INTO audit_log
+                (guild_id, actor_id, target_id, action, before_data, after_data, create_date)
+                VALUES
+                ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING id_audit_log;",
+            self.guild_id,
+            self.actor_id,
+            self.target_id,
+            self.action,
+            self.before_data,
+            self.after_data,
+            self.create_date,
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .id_audit_log;
+
+        self.id_audit_log = id;
+
+        Ok(())
+    }
+
+    pub fn actor_id(&self) -> u64 {
+        self.actor_id as u64
+    }
+
+    pub fn target_id(&self) -> Option<u64> {
+        self.target_id.map(|id| id as u64)
+    }
+
+    pub fn action(&self) -> Option<AuditAction> {
+        AuditAction::from_i32(self.action)
+    }
+}
+
+/// Serializes the state of a row for the audit log.
+pub fn snapshot<T: Serialize>(value: T) -> Option<Value> {
+    serde_json::to_value(value).ok()
+}
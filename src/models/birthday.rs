@@ -1,13 +1,55 @@
-use sqlx::{types::chrono::NaiveDateTime, PgPool};
+use chrono::Datelike;
+use sqlx::{
+    types::chrono::{NaiveDate, NaiveDateTime},
+    PgPool, Postgres, Transaction,
+};
+
+use super::{
+    audit_log::{snapshot, AuditAction, AuditLog},
+    subscription::Subscription,
+    user_data::BirthdayExport,
+};
+
+/// The year of every stored `date`, a leap year so the 29th of February fits.
+pub const PLACEHOLDER_YEAR: i32 = 2000;
+
+/// Decides who is able to see a birthday besides its owner.
+///
+/// A birthday visible to subscribers only is shown to its existing subscribers, but nobody else
+/// can subscribe to it until the owner makes it visible to everyone again.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Everyone = 0,
+    SubscribersOnly = 1,
+    AnnouncementsOnly = 2,
+}
+
+impl Visibility {
+    pub fn from_i32(value: i32) -> Option<Visibility> {
+        match value {
+            0 => Some(Visibility::Everyone),
+            1 => Some(Visibility::SubscribersOnly),
+            2 => Some(Visibility::AnnouncementsOnly),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Birthday {
     pub id_birthday: i32,
     guild_id: i64,
     user_id: i64,
+    /// The month and day of the birthday, always in [`PLACEHOLDER_YEAR`].
     pub date: NaiveDateTime,
+    /// The year of birth, if known.
+    pub year: Option<i32>,
+    pub hide_year: bool,
+    visibility: i32,
     pub create_date: NaiveDateTime,
     pub modify_date: Option<NaiveDateTime>,
+    modified_by: Option<i64>,
 }
 
 impl Birthday {
@@ -15,6 +57,7 @@ impl Birthday {
         guild_id: u64,
         user_id: u64,
         date: NaiveDateTime,
+        year: Option<i32>,
         create_date: NaiveDateTime,
     ) -> Birthday {
         Birthday {
@@ -22,16 +65,84 @@ impl Birthday {
             guild_id: guild_id as i64,
             user_id: user_id as i64,
             date,
+            year,
+            hide_year: false,
+            visibility: Visibility::default() as i32,
             create_date,
             modify_date: None,
+            modified_by: None,
         }
     }
 
-    pub async fn get_all(db: &PgPool) -> Result<Vec<Birthday>, sqlx::Error> {
+    pub async fn get_all_by_month_and_days(
+        db: &PgPool,
+        days: &[(u32, u32)],
+    ) -> Result<Vec<Birthday>, sqlx::Error> {
+        let months: Vec<i32> = days.iter().map(|(month, _)| *month as i32).collect();
+        let days: Vec<i32> = days.iter().map(|(_, day)| *day as i32).collect();
+
+        let birthdays: Vec<Birthday> = sqlx::query_as!(
+            Birthday,
+            "SELECT id_birthday, guild_id, user_id, date, year, hide_year, visibility,
+                create_date, modify_date, modified_by
+                FROM birthday
+                WHERE (EXTRACT(MONTH FROM date)::INTEGER, EXTRACT(DAY FROM date)::INTEGER)
+                IN (SELECT * FROM UNNEST($1::INTEGER[], $2::INTEGER[]));",
+            &months[..],
+            &days[..],
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(birthdays)
+    }
+
+    pub async fn get_all_by_subscriber(
+        db: &PgPool,
+        guild_id: u64,
+        user_id: u64,
+    ) -> Result<Vec<Birthday>, sqlx::Error> {
+        let birthdays: Vec<Birthday> = sqlx::query_as!(
+            Birthday,
+            "SELECT b.id_birthday, b.guild_id, b.user_id, b.date, b.year, b.hide_year, b.visibility,
+                b.create_date, b.modify_date, b.modified_by
+                FROM birthday AS b
+                INNER JOIN subscription AS s
+                ON s.birthday_id = b.id_birthday
+                WHERE s.guild_id = $1
+                AND s.user_id = $2;",
+            (guild_id as i64),
+            (user_id as i64),
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(birthdays)
+    }
+
+    pub async fn get_all_by_guild(db: &PgPool, guild_id: u64) -> Result<Vec<Birthday>, sqlx::Error> {
         let birthdays: Vec<Birthday> = sqlx::query_as!(
             Birthday,
-            "SELECT id_birthday, guild_id, user_id, date, create_date, modify_date
-                FROM birthday;",
+            "SELECT id_birthday, guild_id, user_id, date, year, hide_year, visibility,
+                create_date, modify_date, modified_by
+                FROM birthday
+                WHERE guild_id = $1;",
+            (guild_id as i64),
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(birthdays)
+    }
+
+    pub async fn get_all_by_user(db: &PgPool, user_id: u64) -> Result<Vec<Birthday>, sqlx::Error> {
+        let birthdays: Vec<Birthday> = sqlx::query_as!(
+            Birthday,
+            "SELECT id_birthday, guild_id, user_id, date, year, hide_year, visibility,
+                create_date, modify_date, modified_by
+                FROM birthday
+                WHERE user_id = $1;",
+            (user_id as i64),
         )
         .fetch_all(db)
         .await?;
@@ -42,7 +153,8 @@ impl Birthday {
     pub async fn get_by_id(db: &PgPool, id: i32) -> Result<Option<Birthday>, sqlx::Error> {
         let birthday: Option<Birthday> = sqlx::query_as!(
             Birthday,
-            "SELECT id_birthday, guild_id, user_id, date, create_date, modify_date
+            "SELECT id_birthday, guild_id, user_id, date, year, hide_year, visibility,
+                create_date, modify_date, modified_by
                 FROM birthday
                 WHERE id_birthday = $1;",
             id,
@@ -62,7 +174,8 @@ impl Birthday {
     ) -> Result<Option<Birthday>, sqlx::Error> {
         let birthday: Option<Birthday> = sqlx::query_as!(
             Birthday,
-            "SELECT id_birthday, guild_id, user_id, date, create_date, modify_date
+            "SELECT id_birthday, guild_id, user_id, date, year, hide_year, visibility,
+                create_date, modify_date, modified_by
                 FROM birthday
                 WHERE guild_id = $1
                 AND user_id = $2;",
@@ -77,50 +190,134 @@ impl Birthday {
         Ok(birthday)
     }
 
-    pub async fn insert(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+    /// Returns the birthday and locks it until the end of the transaction.
+    pub async fn get_for_update(
+        tx: &mut Transaction<'_, Postgres>,
+        guild_id: u64,
+        user_id: u64,
+    ) -> Result<Option<Birthday>, sqlx::Error> {
+        let birthday: Option<Birthday> = sqlx::query_as!(
+            Birthday,
+            "SELECT id_birthday, guild_id, user_id, date, year, hide_year, visibility,
+                create_date, modify_date, modified_by
+                FROM birthday
+                WHERE guild_id = $1
+                AND user_id = $2
+                FOR UPDATE;",
+            (guild_id as i64),
+            (user_id as i64),
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        Ok(birthday)
+    }
+
+    /// Inserts the birthday and records the change in the audit log.
+    pub async fn insert(&mut self, db: &PgPool, actor_id: u64) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+        self.insert_tx(&mut tx, actor_id).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Inserts the birthday within a transaction, e.g. to import many birthdays at once.
+    pub async fn insert_tx(
+        &mut self,
+        tx: &mut Transaction<'_, Postgres>,
+        actor_id: u64,
+    ) -> Result<(), sqlx::Error> {
         let id = sqlx::query!(
             "INSERT INTO birthday 
-                (guild_id, user_id, date, create_date)
+                (guild_id, user_id, date, year, hide_year, visibility, create_date, modified_by)
                 VALUES
-                ($1, $2, $3, $4)
+                ($1, $2, $3, $4, $5, $6, $7, $8)
                 RETURNING id_birthday;",
             self.guild_id,
             self.user_id,
             self.date,
+            self.year,
+            self.hide_year,
+            self.visibility,
             self.create_date,
+            self.modified_by,
         )
-        .fetch_one(db)
+        .fetch_one(&mut *tx)
         .await?
         .id_birthday;
 
         self.id_birthday = id;
 
+        AuditLog::new(
+            self.guild_id(),
+            actor_id,
+            Some(self.user_id()),
+            AuditAction::BirthdayCreate,
+            None,
+            snapshot(BirthdayExport::from(&*self)),
+        )
+        .insert(tx)
+        .await?;
+
         Ok(())
     }
 
-    pub async fn update(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+    /// Updates the birthday and records the change in the audit log.
+    pub async fn update(&self, db: &PgPool, actor_id: u64) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+        self.update_tx(&mut tx, actor_id).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Updates the birthday within a transaction, e.g. to import many birthdays at once.
+    pub async fn update_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        actor_id: u64,
+    ) -> Result<(), sqlx::Error> {
+        let before = Birthday::get_for_update(tx, self.guild_id(), self.user_id()).await?;
+
         sqlx::query!(
-            "UPDATE birthday SET date = $1, modify_date = $2
-                WHERE guild_id = $3
-                AND user_id = $4;",
+            "UPDATE birthday SET date = $1, year = $2, hide_year = $3, visibility = $4,
+                modify_date = $5, modified_by = $6
+                WHERE guild_id = $7
+                AND user_id = $8;",
             self.date,
+            self.year,
+            self.hide_year,
+            self.visibility,
             self.modify_date,
+            self.modified_by,
             self.guild_id,
             self.user_id
         )
-        .execute(db)
+        .execute(&mut *tx)
+        .await?;
+
+        AuditLog::new(
+            self.guild_id(),
+            actor_id,
+            Some(self.user_id()),
+            AuditAction::BirthdayUpdate,
+            before.as_ref().and_then(|b| snapshot(BirthdayExport::from(b))),
+            snapshot(BirthdayExport::from(self)),
+        )
+        .insert(tx)
         .await?;
 
         Ok(())
     }
 
-    pub async fn delete(&self, db: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            "DELETE FROM subscription WHERE birthday_id = $1",
-            self.id_birthday
-        )
-        .execute(db)
-        .await?;
+    /// Deletes the birthday with all subscriptions to it and records the changes in the audit log.
+    pub async fn delete(&self, db: &PgPool, actor_id: u64) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        Subscription::delete_all_by_birthday_ids(&mut tx, &[self.id_birthday], actor_id).await?;
 
         sqlx::query!(
             "DELETE FROM birthday
@@ -129,13 +326,163 @@ impl Birthday {
             self.guild_id,
             self.user_id
         )
-        .execute(db)
+        .execute(&mut tx)
         .await?;
 
+        AuditLog::new(
+            self.guild_id(),
+            actor_id,
+            Some(self.user_id()),
+            AuditAction::BirthdayDelete,
+            snapshot(BirthdayExport::from(self)),
+            None,
+        )
+        .insert(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
         Ok(())
     }
 
+    pub fn guild_id(&self) -> u64 {
+        self.guild_id as u64
+    }
+
     pub fn user_id(&self) -> u64 {
         self.user_id as u64
     }
+
+    /// The user who created or last changed the birthday, either its owner or a moderator.
+    pub fn modified_by(&self) -> Option<u64> {
+        self.modified_by.map(|user_id| user_id as u64)
+    }
+
+    pub fn set_modified_by(&mut self, user_id: u64) {
+        self.modified_by = Some(user_id as i64);
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        Visibility::from_i32(self.visibility).unwrap_or_default()
+    }
+
+    pub fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility as i32;
+    }
+
+    /// Checks if the user is allowed to see this birthday, which always applies to its owner.
+    pub fn is_visible_to(&self, user_id: u64, is_subscribed: bool) -> bool {
+        if self.user_id() == user_id {
+            return true;
+        }
+
+        match self.visibility() {
+            Visibility::Everyone => true,
+            Visibility::SubscribersOnly => is_subscribed,
+            Visibility::AnnouncementsOnly => false,
+        }
+    }
+
+    /// Checks if new subscriptions to this birthday are allowed, existing ones are always kept.
+    pub fn accepts_subscriptions(&self) -> bool {
+        self.visibility() == Visibility::Everyone
+    }
+
+    /// Returns the age reached on the given occurrence, if the birth year is known.
+    pub fn age_on(&self, occurrence: NaiveDate) -> Option<i32> {
+        self.year.map(|year| occurrence.year() - year)
+    }
+
+    /// Returns the age for other users, the age is part of the year, which might be hidden.
+    pub fn shared_age_on(&self, occurrence: NaiveDate) -> Option<i32> {
+        match self.hide_year {
+            true => None,
+            false => self.age_on(occurrence),
+        }
+    }
+
+    /// Returns the date for other users, in [`PLACEHOLDER_YEAR`] if the year is hidden or unknown.
+    pub fn shared_date(&self) -> NaiveDate {
+        let date = self.date.date();
+
+        match self.year {
+            Some(year) if !self.hide_year => date.with_year(year).unwrap_or(date),
+            _ => date,
+        }
+    }
+
+    /// Formats the date for other users, leaving out the year if it is hidden.
+    pub fn display_date(&self) -> String {
+        self.format_date(!self.hide_year)
+    }
+
+    /// Formats the date, leaving out the year if it is unknown or not requested.
+    pub fn format_date(&self, with_year: bool) -> String {
+        match self.year {
+            Some(year) if with_year => format!("{}-{}", year, self.date.format("%m-%d")),
+            _ => self.date.format("%m-%d").to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn birthday(day: u32, month: u32, year: Option<i32>) -> Birthday {
+        let date = date(PLACEHOLDER_YEAR, month, day).and_hms_opt(0, 0, 0).unwrap();
+
+        Birthday::new(1, 1, date, year, date)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn leap_day_age_in_non_leap_years() {
+        let birthday = birthday(29, 2, Some(2000));
+
+        assert_eq!(birthday.age_on(date(2023, 2, 28)), Some(23));
+        assert_eq!(birthday.age_on(date(2023, 3, 1)), Some(23));
+        assert_eq!(birthday.age_on(date(2024, 2, 29)), Some(24));
+    }
+
+    #[test]
+    fn age_at_the_turn_of_the_year() {
+        assert_eq!(birthday(31, 12, Some(1990)).age_on(date(2023, 12, 31)), Some(33));
+        assert_eq!(birthday(1, 1, Some(1990)).age_on(date(2024, 1, 1)), Some(34));
+    }
+
+    #[test]
+    fn no_age_without_year() {
+        let birthday = birthday(15, 6, None);
+
+        assert_eq!(birthday.age_on(date(2023, 6, 15)), None);
+        assert_eq!(birthday.shared_age_on(date(2023, 6, 15)), None);
+    }
+
+    #[test]
+    fn no_shared_age_with_hidden_year() {
+        let mut birthday = birthday(15, 6, Some(1990));
+        birthday.hide_year = true;
+
+        assert_eq!(birthday.age_on(date(2023, 6, 15)), Some(33));
+        assert_eq!(birthday.shared_age_on(date(2023, 6, 15)), None);
+    }
+
+    #[test]
+    fn subscribers_only_is_closed_to_new_subscribers() {
+        let mut birthday = birthday(15, 6, None);
+        birthday.set_visibility(Visibility::SubscribersOnly);
+
+        assert!(birthday.is_visible_to(1, false));
+        assert!(birthday.is_visible_to(2, true));
+        assert!(!birthday.is_visible_to(2, false));
+        assert!(!birthday.accepts_subscriptions());
+
+        birthday.set_visibility(Visibility::Everyone);
+
+        assert!(birthday.accepts_subscriptions());
+    }
 }
@@ -0,0 +1,102 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+
+/// The number of characters of a token, about 190 bits of randomness.
+const TOKEN_LENGTH: usize = 32;
+
+/// The secret part of the url of the calendar feed of a user on a guild.
+///
+/// Only the hash of the token is stored, the token itself is only known to the user.
+pub struct CalendarToken {
+    pub id_calendar_token: i32,
+    guild_id: i64,
+    user_id: i64,
+    token_hash: String,
+    pub create_date: NaiveDateTime,
+}
+
+impl CalendarToken {
+    pub fn new(
+        guild_id: u64,
+        user_id: u64,
+        token: &str,
+        create_date: NaiveDateTime,
+    ) -> CalendarToken {
+        CalendarToken {
+            id_calendar_token: 0,
+            guild_id: guild_id as i64,
+            user_id: user_id as i64,
+            token_hash: hash_token(token),
+            create_date,
+        }
+    }
+
+    /// Generates a new random secret for the url.
+    pub fn gen_token() -> String {
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect()
+    }
+
+    pub async fn get_by_token(
+        db: &PgPool,
+        token: &str,
+    ) -> Result<Option<CalendarToken>, sqlx::Error> {
+        let calendar_token: Option<CalendarToken> = sqlx::query_as!(
+            CalendarToken,
+            "SELECT id_calendar_token, guild_id, user_id, token_hash, create_date
+                FROM calendar_token
+                WHERE token_hash = $1;",
+            hash_token(token)
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        Ok(calendar_token)
+    }
+
+    /// Inserts the token, replacing the previous token of the user on the guild.
+    pub async fn save(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let id = sqlx::query!(
+            "INSERT INTO calendar_token
+                (guild_id, user_id, token_hash, create_date)
+                VALUES
+                ($1, $2, $3, $4)
+                ON CONFLICT (guild_id, user_id)
+                DO UPDATE SET token_hash = EXCLUDED.token_hash, create_date = EXCLUDED.create_date
+                RETURNING id_calendar_token;",
+            self.guild_id,
+            self.user_id,
+            self.token_hash,
+            self.create_date,
+        )
+        .fetch_one(db)
+        .await?
+        .id_calendar_token;
+
+        self.id_calendar_token = id;
+
+        Ok(())
+    }
+
+    pub fn guild_id(&self) -> u64 {
+        self.guild_id as u64
+    }
+
+    pub fn user_id(&self) -> u64 {
+        self.user_id as u64
+    }
+}
+
+/// Returns the sha-256 hash of a token as lowercase hex, like the migration of existing tokens.
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
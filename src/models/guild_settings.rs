@@ -0,0 +1,183 @@
+use serde::Serialize;
+use sqlx::{types::chrono::NaiveDateTime, PgPool, Postgres, Transaction};
+
+use crate::recurrence::LeapDayPolicy;
+
+use super::audit_log::{snapshot, AuditAction, AuditLog};
+
+/// The ages celebrated as milestones if a guild did not configure its own.
+pub const DEFAULT_MILESTONES: [i32; 10] = [18, 21, 30, 40, 50, 60, 70, 80, 90, 100];
+pub const MAX_MILESTONE_AGE: i32 = 150;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GuildSettings {
+    pub id_guild_settings: i32,
+    guild_id: i64,
+    leap_day_policy: i32,
+    announcement_channel_id: Option<i64>,
+    birthday_role_id: Option<i64>,
+    pub milestones: Vec<i32>,
+    pub dm_template: Option<String>,
+    pub announcement_template: Option<String>,
+    pub create_date: NaiveDateTime,
+    pub modify_date: Option<NaiveDateTime>,
+}
+
+impl GuildSettings {
+    pub fn new(guild_id: u64, create_date: NaiveDateTime) -> GuildSettings {
+        GuildSettings {
+            id_guild_settings: 0,
+            guild_id: guild_id as i64,
+            leap_day_policy: LeapDayPolicy::default() as i32,
+            announcement_channel_id: None,
+            birthday_role_id: None,
+            milestones: DEFAULT_MILESTONES.to_vec(),
+            dm_template: None,
+            announcement_template: None,
+            create_date,
+            modify_date: None,
+        }
+    }
+
+    pub async fn get(db: &PgPool, guild_id: u64) -> Result<Option<GuildSettings>, sqlx::Error> {
+        let settings: Option<GuildSettings> = sqlx::query_as!(
+            GuildSettings,
+            "SELECT id_guild_settings, guild_id, leap_day_policy, announcement_channel_id, birthday_role_id,
+                milestones, dm_template, announcement_template, create_date, modify_date
+                FROM guild_settings
+                WHERE guild_id = $1;",
+            (guild_id as i64),
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        Ok(settings)
+    }
+
+    pub async fn get_or_default(
+        db: &PgPool,
+        guild_id: u64,
+        create_date: NaiveDateTime,
+    ) -> Result<GuildSettings, sqlx::Error> {
+        Ok(GuildSettings::get(db, guild_id)
+            .await?
+            .unwrap_or_else(|| GuildSettings::new(guild_id, create_date)))
+    }
+
+    async fn insert(&mut self, tx: &mut Transaction<'_, Postgres>) -> Result<(), sqlx::Error> {
+        let id = sqlx::query!(
+            "INSERT INTO guild_settings
+                (guild_id, leap_day_policy, announcement_channel_id, birthday_role_id, milestones,
+                dm_template, announcement_template, create_date)
+                VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id_guild_settings;",
+            self.guild_id,
+            self.leap_day_policy,
+            self.announcement_channel_id,
+            self.birthday_role_id,
+            &self.milestones[..],
+            self.dm_template,
+            self.announcement_template,
+            self.create_date,
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .id_guild_settings;
+
+        self.id_guild_settings = id;
+
+        Ok(())
+    }
+
+    async fn update(&self, tx: &mut Transaction<'_, Postgres>) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE guild_settings
+                SET leap_day_policy = $1, announcement_channel_id = $2, birthday_role_id = $3, milestones = $4,
+                dm_template = $5, announcement_template = $6, modify_date = $7
+                WHERE guild_id = $8;",
+            self.leap_day_policy,
+            self.announcement_channel_id,
+            self.birthday_role_id,
+            &self.milestones[..],
+            self.dm_template,
+            self.announcement_template,
+            self.modify_date,
+            self.guild_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Inserts or updates the settings and records the change in the audit log.
+    pub async fn save(&mut self, db: &PgPool, actor_id: u64) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        let before: Option<GuildSettings> = sqlx::query_as!(
+            GuildSettings,
+            "SELECT id_guild_settings, guild_id, leap_day_policy, announcement_channel_id, birthday_role_id,
+                milestones, dm_template, announcement_template, create_date, modify_date
+                FROM guild_settings
+                WHERE guild_id = $1
+                FOR UPDATE;",
+            self.guild_id,
+        )
+        .fetch_all(&mut tx)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        if self.id_guild_settings == 0 {
+            self.insert(&mut tx).await?;
+        } else {
+            self.update(&mut tx).await?;
+        }
+
+        AuditLog::new(
+            self.guild_id as u64,
+            actor_id,
+            None,
+            AuditAction::SettingsUpdate,
+            before.as_ref().and_then(snapshot),
+            snapshot(&*self),
+        )
+        .insert(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub fn leap_day_policy(&self) -> LeapDayPolicy {
+        LeapDayPolicy::from_i32(self.leap_day_policy).unwrap_or_default()
+    }
+
+    pub fn set_leap_day_policy(&mut self, policy: LeapDayPolicy) {
+        self.leap_day_policy = policy as i32;
+    }
+
+    pub fn announcement_channel_id(&self) -> Option<u64> {
+        self.announcement_channel_id.map(|id| id as u64)
+    }
+
+    pub fn set_announcement_channel_id(&mut self, channel_id: Option<u64>) {
+        self.announcement_channel_id = channel_id.map(|id| id as i64);
+    }
+
+    pub fn is_milestone(&self, age: i32) -> bool {
+        self.milestones.contains(&age)
+    }
+
+    pub fn birthday_role_id(&self) -> Option<u64> {
+        self.birthday_role_id.map(|id| id as u64)
+    }
+
+    pub fn set_birthday_role_id(&mut self, role_id: Option<u64>) {
+        self.birthday_role_id = role_id.map(|id| id as i64);
+    }
+}
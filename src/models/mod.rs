@@ -1,2 +1,11 @@
+pub mod announcement;
+pub mod audit_log;
 pub mod birthday;
+pub mod calendar_token;
+pub mod guild_settings;
+pub mod pending_import;
+pub mod role_assignment;
+pub mod scheduler_state;
 pub mod subscription;
+pub mod user_data;
+pub mod user_settings;
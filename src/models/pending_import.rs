@@ -0,0 +1,169 @@
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+
+use super::{birthday::Birthday, user_settings::UserSettings};
+
+/// A validated row of an import, stored until the import is confirmed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportRow {
+    pub user_id: u64,
+    pub date: NaiveDateTime,
+    pub year: Option<i32>,
+    pub timezone: Option<String>,
+}
+
+/// The valid rows of an uploaded file, waiting for the confirmation of the user who uploaded it.
+pub struct PendingImport {
+    pub id_pending_import: i32,
+    guild_id: i64,
+    user_id: i64,
+    import_rows: Value,
+    pub create_date: NaiveDateTime,
+}
+
+impl PendingImport {
+    pub fn new(
+        guild_id: u64,
+        user_id: u64,
+        rows: &[ImportRow],
+        create_date: NaiveDateTime,
+    ) -> PendingImport {
+        PendingImport {
+            id_pending_import: 0,
+            guild_id: guild_id as i64,
+            user_id: user_id as i64,
+            import_rows: serde_json::to_value(rows).unwrap_or_default(),
+            create_date,
+        }
+    }
+
+    pub async fn get(
+        db: &PgPool,
+        id: i32,
+        guild_id: u64,
+        user_id: u64,
+    ) -> Result<Option<PendingImport>, sqlx::Error> {
+        let import: Option<PendingImport> = sqlx::query_as!(
+            PendingImport,
+            "SELECT id_pending_import, guild_id, user_id, import_rows, create_date
+                FROM pending_import
+                WHERE id_pending_import = $1
+                AND guild_id = $2
+                AND user_id = $3;",
+            id,
+            (guild_id as i64),
+            (user_id as i64),
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        Ok(import)
+    }
+
+    /// Inserts the import and removes those which were never confirmed before `expiry_date`.
+    pub async fn insert(
+        &mut self,
+        db: &PgPool,
+        expiry_date: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM pending_import WHERE create_date < $1;",
+            expiry_date
+        )
+        .execute(db)
+        .await?;
+
+        let id = sqlx::query!(
+            "INSERT INTO pending_import
+                (guild_id, user_id, import_rows, create_date)
+                VALUES
+                ($1, $2, $3, $4)
+                RETURNING id_pending_import;",
+            self.guild_id,
+            self.user_id,
+            self.import_rows,
+            self.create_date,
+        )
+        .fetch_one(db)
+        .await?
+        .id_pending_import;
+
+        self.id_pending_import = id;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM pending_import WHERE id_pending_import = $1;",
+            self.id_pending_import
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sets all birthdays of the import in one transaction and deletes the import.
+    ///
+    /// Existing birthdays keep their privacy settings, the timezone of a row is only used for users
+    /// who have not set their own timezone yet.
+    /// Returns the number of birthdays set.
+    pub async fn commit(
+        &self,
+        db: &PgPool,
+        modify_date: NaiveDateTime,
+    ) -> Result<usize, sqlx::Error> {
+        let rows = self.rows();
+        let actor_id = self.user_id as u64;
+        let mut tx = db.begin().await?;
+
+        for row in rows.iter() {
+            if let Some(timezone) = row.timezone.as_deref().and_then(|tz| tz.parse::<Tz>().ok()) {
+                let mut settings = UserSettings::new(row.user_id, modify_date);
+                settings.set_timezone(timezone);
+                settings.insert_or_keep_tx(&mut tx).await?;
+            }
+
+            match Birthday::get_for_update(&mut tx, self.guild_id as u64, row.user_id).await? {
+                Some(mut birthday) => {
+                    birthday.date = row.date;
+                    birthday.year = row.year;
+                    birthday.modify_date = Some(modify_date);
+                    birthday.set_modified_by(actor_id);
+                    birthday.update_tx(&mut tx, actor_id).await?;
+                }
+                None => {
+                    let mut birthday = Birthday::new(
+                        self.guild_id as u64,
+                        row.user_id,
+                        row.date,
+                        row.year,
+                        modify_date,
+                    );
+                    birthday.set_modified_by(actor_id);
+                    birthday.insert_tx(&mut tx, actor_id).await?;
+                }
+            }
+        }
+
+        sqlx::query!(
+            "DELETE FROM pending_import WHERE id_pending_import = $1;",
+            self.id_pending_import
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(rows.len())
+    }
+
+    pub fn rows(&self) -> Vec<ImportRow> {
+        serde_json::from_value(self.import_rows.clone()).unwrap_or_default()
+    }
+}
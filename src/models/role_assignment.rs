@@ -0,0 +1,155 @@
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+
+use crate::retry;
+
+/// The birthday role of a member for a year, recorded before it is assigned.
+pub struct RoleAssignment {
+    pub id_role_assignment: i32,
+    pub birthday_id: i32,
+    guild_id: i64,
+    user_id: i64,
+    role_id: i64,
+    pub current_year: i32,
+    pub remove_date: NaiveDateTime,
+    pub removed_date: Option<NaiveDateTime>,
+    pub attempts: i32,
+    /// The earliest date of the next attempt after a failed one, empty once assigned or given up.
+    pub retry_date: Option<NaiveDateTime>,
+    pub create_date: NaiveDateTime,
+}
+
+impl RoleAssignment {
+    pub fn new(
+        birthday_id: i32,
+        guild_id: u64,
+        user_id: u64,
+        role_id: u64,
+        current_year: i32,
+        remove_date: NaiveDateTime,
+        create_date: NaiveDateTime,
+    ) -> RoleAssignment {
+        RoleAssignment {
+            id_role_assignment: 0,
+            birthday_id,
+            guild_id: guild_id as i64,
+            user_id: user_id as i64,
+            role_id: role_id as i64,
+            current_year,
+            remove_date,
+            removed_date: None,
+            attempts: 0,
+            retry_date: None,
+            create_date,
+        }
+    }
+
+    pub async fn get(
+        db: &PgPool,
+        birthday_id: i32,
+        current_year: i32,
+    ) -> Result<Option<RoleAssignment>, sqlx::Error> {
+        let assignment: Option<RoleAssignment> = sqlx::query_as!(
+            RoleAssignment,
+            "SELECT id_role_assignment, birthday_id, guild_id, user_id, role_id, current_year,
+                remove_date, removed_date, attempts, retry_date, create_date
+                FROM role_assignments
+                WHERE birthday_id = $1
+                AND current_year = $2;",
+            birthday_id,
+            current_year,
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        Ok(assignment)
+    }
+
+    pub async fn get_all_expired(
+        db: &PgPool,
+        now: NaiveDateTime,
+    ) -> Result<Vec<RoleAssignment>, sqlx::Error> {
+        let assignments: Vec<RoleAssignment> = sqlx::query_as!(
+            RoleAssignment,
+            "SELECT id_role_assignment, birthday_id, guild_id, user_id, role_id, current_year,
+                remove_date, removed_date, attempts, retry_date, create_date
+                FROM role_assignments
+                WHERE remove_date <= $1
+                AND removed_date IS NULL;",
+            now,
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(assignments)
+    }
+
+    /// Inserts the assignment or updates the attempts of an existing one.
+    pub async fn save(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let id = sqlx::query!(
+            "INSERT INTO role_assignments
+                (birthday_id, guild_id, user_id, role_id, current_year, remove_date, attempts, retry_date, create_date)
+                VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                ON CONFLICT (birthday_id, current_year)
+                DO UPDATE SET attempts = EXCLUDED.attempts, retry_date = EXCLUDED.retry_date
+                RETURNING id_role_assignment;",
+            self.birthday_id,
+            self.guild_id,
+            self.user_id,
+            self.role_id,
+            self.current_year,
+            self.remove_date,
+            self.attempts,
+            self.retry_date,
+            self.create_date,
+        )
+        .fetch_one(db)
+        .await?
+        .id_role_assignment;
+
+        self.id_role_assignment = id;
+
+        Ok(())
+    }
+
+    pub async fn update(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE role_assignments SET removed_date = $1
+                WHERE id_role_assignment = $2;",
+            self.removed_date,
+            self.id_role_assignment,
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn is_retry_due(&self, now: NaiveDateTime) -> bool {
+        self.retry_date.is_some_and(|retry_date| retry_date <= now)
+    }
+
+    /// Counts an attempt, which is treated as successful until [`RoleAssignment::record_failure`].
+    pub fn record_attempt(&mut self) {
+        self.attempts += 1;
+        self.retry_date = None;
+    }
+
+    pub fn record_failure(&mut self, failure_date: NaiveDateTime) {
+        self.retry_date = retry::next_attempt_date(self.attempts, failure_date);
+    }
+
+    pub fn guild_id(&self) -> u64 {
+        self.guild_id as u64
+    }
+
+    pub fn user_id(&self) -> u64 {
+        self.user_id as u64
+    }
+
+    pub fn role_id(&self) -> u64 {
+        self.role_id as u64
+    }
+}
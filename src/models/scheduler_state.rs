@@ -0,0 +1,51 @@
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+
+/// The scheduler state is stored in a single row with this id.
+const SCHEDULER_STATE_ID: i32 = 1;
+
+pub struct SchedulerState {
+    pub id_scheduler_state: i32,
+    pub last_run_date: NaiveDateTime,
+}
+
+impl SchedulerState {
+    pub fn new(last_run_date: NaiveDateTime) -> SchedulerState {
+        SchedulerState {
+            id_scheduler_state: SCHEDULER_STATE_ID,
+            last_run_date,
+        }
+    }
+
+    pub async fn get(db: &PgPool) -> Result<Option<SchedulerState>, sqlx::Error> {
+        let state: Option<SchedulerState> = sqlx::query_as!(
+            SchedulerState,
+            "SELECT id_scheduler_state, last_run_date
+                FROM scheduler_state
+                WHERE id_scheduler_state = $1;",
+            SCHEDULER_STATE_ID,
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        Ok(state)
+    }
+
+    pub async fn save(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT INTO scheduler_state
+                (id_scheduler_state, last_run_date)
+                VALUES
+                ($1, $2)
+                ON CONFLICT (id_scheduler_state)
+                DO UPDATE SET last_run_date = EXCLUDED.last_run_date;",
+            self.id_scheduler_state,
+            self.last_run_date,
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
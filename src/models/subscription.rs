@@ -1,10 +1,23 @@
-use sqlx::{types::chrono::NaiveDateTime, PgPool};
+use sqlx::{
+    types::chrono::{NaiveDateTime, NaiveTime},
+    PgPool, Postgres, Transaction,
+};
+
+use super::{
+    audit_log::{snapshot, AuditAction, AuditLog},
+    user_data::SubscriptionExport,
+};
+
+/// The maximum number of days a reminder can be sent before a birthday.
+pub const MAX_REMINDER_DAYS: i32 = 30;
 
 pub struct Subscription {
     pub id_subscription: i32,
     guild_id: i64,
     user_id: i64,
     pub birthday_id: i32,
+    pub notify_time: Option<NaiveTime>,
+    pub reminder_days: Vec<i32>,
     pub create_date: NaiveDateTime,
     pub modify_date: Option<NaiveDateTime>,
 }
@@ -13,6 +26,7 @@ pub struct SendNotification {
     pub id_send_notification: i32,
     pub subscription_id: i32,
     pub current_year: i32,
+    pub days_before: i32,
     pub create_date: NaiveDateTime,
 }
 
@@ -21,6 +35,8 @@ impl Subscription {
         guild_id: u64,
         user_id: u64,
         birthday_id: i32,
+        notify_time: Option<NaiveTime>,
+        reminder_days: Vec<i32>,
         create_date: NaiveDateTime,
     ) -> Subscription {
         Subscription {
@@ -28,6 +44,8 @@ impl Subscription {
             guild_id: guild_id as i64,
             user_id: user_id as i64,
             birthday_id,
+            notify_time,
+            reminder_days,
             create_date,
             modify_date: None,
         }
@@ -41,7 +59,7 @@ impl Subscription {
     ) -> Result<Option<Subscription>, sqlx::Error> {
         let subscription: Option<Subscription> = sqlx::query_as!(
             Subscription,
-            "SELECT id_subscription, guild_id, user_id, birthday_id, create_date, modify_date
+            "SELECT id_subscription, guild_id, user_id, birthday_id, notify_time, reminder_days, create_date, modify_date
                 FROM subscription
                 WHERE guild_id = $1
                 AND user_id = $2
@@ -58,6 +76,42 @@ impl Subscription {
         Ok(subscription)
     }
 
+    pub async fn get_all_by_user(
+        db: &PgPool,
+        user_id: u64,
+    ) -> Result<Vec<Subscription>, sqlx::Error> {
+        let subscriptions: Vec<Subscription> = sqlx::query_as!(
+            Subscription,
+            "SELECT id_subscription, guild_id, user_id, birthday_id, notify_time, reminder_days, create_date, modify_date
+                FROM subscription
+                WHERE user_id = $1;",
+            (user_id as i64)
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(subscriptions)
+    }
+
+    pub async fn get_all_by_birthday_user(
+        db: &PgPool,
+        user_id: u64,
+    ) -> Result<Vec<Subscription>, sqlx::Error> {
+        let subscriptions: Vec<Subscription> = sqlx::query_as!(
+            Subscription,
+            "SELECT s.id_subscription, s.guild_id, s.user_id, s.birthday_id, s.notify_time, s.reminder_days, s.create_date, s.modify_date
+                FROM subscription AS s
+                INNER JOIN birthday AS b
+                ON s.birthday_id = b.id_birthday
+                WHERE b.user_id = $1;",
+            (user_id as i64)
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(subscriptions)
+    }
+
     pub async fn get_all_by_guild_and_user(
         db: &PgPool,
         guild_id: u64,
@@ -65,7 +119,7 @@ impl Subscription {
     ) -> Result<Vec<Subscription>, sqlx::Error> {
         let subscriptions: Vec<Subscription> = sqlx::query_as!(
             Subscription,
-            "SELECT id_subscription, guild_id, user_id, birthday_id, create_date, modify_date
+            "SELECT id_subscription, guild_id, user_id, birthday_id, notify_time, reminder_days, create_date, modify_date
                 FROM subscription
                 WHERE guild_id = $1
                 AND user_id = $2;",
@@ -82,17 +136,22 @@ impl Subscription {
         db: &PgPool,
         birthday_id: i32,
         year: i32,
+        days_before: i32,
     ) -> Result<Vec<Subscription>, sqlx::Error> {
         let subscriptions: Vec<Subscription> = sqlx::query_as!(
             Subscription,
-            "SELECT s.id_subscription, s.guild_id, s.user_id, s.birthday_id, s.create_date, s.modify_date
+            "SELECT s.id_subscription, s.guild_id, s.user_id, s.birthday_id, s.notify_time, s.reminder_days, s.create_date, s.modify_date
                 FROM subscription AS s
                 LEFT JOIN send_notifications AS sn
-                ON s.id_subscription = sn.subscription_id AND sn.current_year = $1
+                ON s.id_subscription = sn.subscription_id
+                AND sn.current_year = $1
+                AND sn.days_before = $3
                 WHERE s.birthday_id = $2
+                AND $3 = ANY(s.reminder_days)
                 AND sn.id_send_notification is NULL;",
             year,
             birthday_id,
+            days_before,
         )
         .fetch_all(db)
         .await?;
@@ -100,62 +159,201 @@ impl Subscription {
         Ok(subscriptions)
     }
 
-    pub async fn insert(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+    /// Inserts the subscription and records the change in the audit log.
+    pub async fn insert(&mut self, db: &PgPool, actor_id: u64) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+
         let id = sqlx::query!(
             "INSERT INTO subscription 
-                (guild_id, user_id, birthday_id, create_date)
+                (guild_id, user_id, birthday_id, notify_time, reminder_days, create_date)
                 VALUES
-                ($1, $2, $3, $4)
+                ($1, $2, $3, $4, $5, $6)
                 RETURNING id_subscription;",
             self.guild_id,
             self.user_id,
             self.birthday_id,
+            self.notify_time,
+            &self.reminder_days[..],
             self.create_date,
         )
-        .fetch_one(db)
+        .fetch_one(&mut tx)
         .await?
         .id_subscription;
 
         self.id_subscription = id;
 
+        AuditLog::new(
+            self.guild_id(),
+            actor_id,
+            Some(self.user_id()),
+            AuditAction::SubscriptionCreate,
+            None,
+            snapshot(SubscriptionExport::from(&*self)),
+        )
+        .insert(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
         Ok(())
     }
 
-    pub async fn delete(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+    /// Updates the subscription and records the change in the audit log.
+    pub async fn update(&self, db: &PgPool, actor_id: u64) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        let before: Option<Subscription> = sqlx::query_as!(
+            Subscription,
+            "SELECT id_subscription, guild_id, user_id, birthday_id, notify_time, reminder_days, create_date, modify_date
+                FROM subscription
+                WHERE id_subscription = $1
+                FOR UPDATE;",
+            self.id_subscription,
+        )
+        .fetch_all(&mut tx)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        sqlx::query!(
+            "UPDATE subscription SET notify_time = $1, reminder_days = $2, modify_date = $3
+                WHERE id_subscription = $4;",
+            self.notify_time,
+            &self.reminder_days[..],
+            self.modify_date,
+            self.id_subscription,
+        )
+        .execute(&mut tx)
+        .await?;
+
+        AuditLog::new(
+            self.guild_id(),
+            actor_id,
+            Some(self.user_id()),
+            AuditAction::SubscriptionUpdate,
+            before.as_ref().and_then(|s| snapshot(SubscriptionExport::from(s))),
+            snapshot(SubscriptionExport::from(self)),
+        )
+        .insert(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Deletes the subscription and records the change in the audit log.
+    pub async fn delete(&self, db: &PgPool, actor_id: u64) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+
         sqlx::query!(
             "DELETE FROM subscription WHERE id_subscription = $1",
             self.id_subscription
         )
-        .execute(db)
+        .execute(&mut tx)
         .await?;
 
+        AuditLog::new(
+            self.guild_id(),
+            actor_id,
+            Some(self.user_id()),
+            AuditAction::SubscriptionDelete,
+            snapshot(SubscriptionExport::from(self)),
+            None,
+        )
+        .insert(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
         Ok(())
     }
-    
+
+    /// Deletes all subscriptions to the birthdays within the transaction which deletes them,
+    /// and records an entry in the audit log for every subscription, as they belong to other users.
+    pub async fn delete_all_by_birthday_ids(
+        tx: &mut Transaction<'_, Postgres>,
+        birthday_ids: &[i32],
+        actor_id: u64,
+    ) -> Result<u64, sqlx::Error> {
+        let subscriptions: Vec<Subscription> = sqlx::query_as!(
+            Subscription,
+            "DELETE FROM subscription
+                WHERE birthday_id = ANY($1)
+                RETURNING id_subscription, guild_id, user_id, birthday_id, notify_time, reminder_days, create_date, modify_date;",
+            birthday_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for subscription in &subscriptions {
+            AuditLog::new(
+                subscription.guild_id(),
+                actor_id,
+                Some(subscription.user_id()),
+                AuditAction::SubscriptionDelete,
+                snapshot(SubscriptionExport::from(subscription)),
+                None,
+            )
+            .insert(tx)
+            .await?;
+        }
+
+        Ok(subscriptions.len() as u64)
+    }
+
+    pub fn guild_id(&self) -> u64 {
+        self.guild_id as u64
+    }
+
     pub fn user_id(&self) -> u64 {
         self.user_id as u64
     }
 }
 
 impl SendNotification {
-    pub fn new(subscription_id: i32, current_year: i32, create_date: NaiveDateTime) -> Self {
+    pub fn new(
+        subscription_id: i32,
+        current_year: i32,
+        days_before: i32,
+        create_date: NaiveDateTime,
+    ) -> Self {
         Self {
             id_send_notification: 0,
-            subscription_id: subscription_id,
-            current_year: current_year,
+            subscription_id,
+            current_year,
+            days_before,
             create_date,
         }
     }
 
+    pub async fn get_all_by_subscription_ids(
+        db: &PgPool,
+        subscription_ids: &[i32],
+    ) -> Result<Vec<SendNotification>, sqlx::Error> {
+        let notifications: Vec<SendNotification> = sqlx::query_as!(
+            SendNotification,
+            "SELECT id_send_notification, subscription_id, current_year, days_before, create_date
+                FROM send_notifications
+                WHERE subscription_id = ANY($1);",
+            subscription_ids
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(notifications)
+    }
+
     pub async fn insert(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
         let id = sqlx::query!(
             "INSERT INTO send_notifications 
-                (subscription_id, current_year, create_date)
+                (subscription_id, current_year, days_before, create_date)
                 VALUES
-                ($1, $2, $3)
+                ($1, $2, $3, $4)
                 RETURNING id_send_notification;",
             self.subscription_id,
             self.current_year,
+            self.days_before,
             self.create_date,
         )
         .fetch_one(db)
@@ -166,4 +364,102 @@ impl SendNotification {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::types::chrono::{NaiveDate, Utc};
+
+    use crate::commands::CommandError;
+    use crate::models::birthday::Birthday;
+    use crate::models::user_data::UserDataErasure;
+
+    use super::*;
+
+    const GUILD_ID: u64 = 1;
+    const SUBSCRIBER_ID: u64 = 10;
+
+    async fn insert_birthday(db: &PgPool, user_id: u64) -> Birthday {
+        let date = NaiveDate::from_ymd_opt(2000, 5, 17)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .unwrap();
+        let mut birthday = Birthday::new(GUILD_ID, user_id, date, None, Utc::now().naive_utc());
+        birthday.insert(db, user_id).await.unwrap();
+
+        birthday
+    }
+
+    fn new_subscription(birthday: &Birthday) -> Subscription {
+        Subscription::new(
+            GUILD_ID,
+            SUBSCRIBER_ID,
+            birthday.id_birthday,
+            None,
+            vec![0],
+            Utc::now().naive_utc(),
+        )
+    }
+
+    #[sqlx::test]
+    async fn subscribes_to_several_birthdays_on_one_guild(db: PgPool) {
+        let first = insert_birthday(&db, 20).await;
+        let second = insert_birthday(&db, 21).await;
+
+        new_subscription(&first).insert(&db, SUBSCRIBER_ID).await.unwrap();
+        new_subscription(&second).insert(&db, SUBSCRIBER_ID).await.unwrap();
+
+        let subscriptions = Subscription::get_all_by_guild_and_user(&db, GUILD_ID, SUBSCRIBER_ID)
+            .await
+            .unwrap();
+        let mut birthday_ids: Vec<i32> = subscriptions.iter().map(|s| s.birthday_id).collect();
+        birthday_ids.sort();
+
+        assert_eq!(birthday_ids, vec![first.id_birthday, second.id_birthday]);
+    }
+
+    #[sqlx::test]
+    async fn duplicate_subscription_already_exists(db: PgPool) {
+        let birthday = insert_birthday(&db, 20).await;
+
+        new_subscription(&birthday).insert(&db, SUBSCRIBER_ID).await.unwrap();
+        let why = new_subscription(&birthday)
+            .insert(&db, SUBSCRIBER_ID)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(CommandError::from_db(why), CommandError::AlreadyExists));
+    }
+
+    #[sqlx::test]
+    async fn deleting_a_birthday_records_its_subscriptions(db: PgPool) {
+        let birthday = insert_birthday(&db, 20).await;
+        new_subscription(&birthday).insert(&db, SUBSCRIBER_ID).await.unwrap();
+
+        birthday.delete(&db, 30).await.unwrap();
+
+        let entries = AuditLog::get_page(&db, GUILD_ID, Some(SUBSCRIBER_ID), 10, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(entries[0].action(), Some(AuditAction::SubscriptionDelete));
+        assert_eq!(entries[0].actor_id(), 30);
+        assert!(entries[0].before_data.is_some());
+    }
+
+    #[sqlx::test]
+    async fn erasing_a_user_records_the_subscriptions_of_others(db: PgPool) {
+        let birthday = insert_birthday(&db, 20).await;
+        new_subscription(&birthday).insert(&db, SUBSCRIBER_ID).await.unwrap();
+
+        let erasure = UserDataErasure::execute(&db, 20).await.unwrap();
+
+        let entries = AuditLog::get_page(&db, GUILD_ID, Some(SUBSCRIBER_ID), 10, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(erasure.subscribers, 1);
+        assert_eq!(entries[0].action(), Some(AuditAction::SubscriptionDelete));
+        assert_eq!(entries[0].actor_id(), 20);
+        assert!(entries[0].before_data.is_some());
+    }
+}
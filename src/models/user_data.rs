@@ -0,0 +1,257 @@
+use serde::Serialize;
+use sqlx::{
+    types::chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc},
+    PgPool,
+};
+
+use super::{
+    audit_log::AuditAction,
+    birthday::Birthday,
+    subscription::{SendNotification, Subscription},
+    user_settings::UserSettings,
+};
+
+/// The version of the export schema, increased on every incompatible change.
+const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// The number of rows deleted when erasing all data of a user.
+pub struct UserDataErasure {
+    pub birthdays: u64,
+    pub subscriptions: u64,
+    pub subscribers: u64,
+    pub notifications: u64,
+}
+
+impl UserDataErasure {
+    /// Deletes all data about a user across every guild in one transaction.
+    ///
+    /// Role assignments which have not been removed yet are kept, so the
+    /// birthday role is still taken away from the user. The audit log keeps
+    /// its entries about the user, but without the stored birthdays and
+    /// subscriptions, and gets an entry for every guild the user had data on
+    /// and for every subscription of other users to the deleted birthdays.
+    pub async fn execute(db: &PgPool, user_id: u64) -> Result<UserDataErasure, sqlx::Error> {
+        let user_id = user_id as i64;
+        let mut tx = db.begin().await?;
+
+        sqlx::query!(
+            "INSERT INTO audit_log
+                (guild_id, actor_id, target_id, action, create_date)
+                SELECT guild_id, $1, $1, $2, $3
+                FROM (
+                    SELECT guild_id FROM birthday WHERE user_id = $1
+                    UNION
+                    SELECT guild_id FROM subscription WHERE user_id = $1
+                ) AS guilds;",
+            user_id,
+            AuditAction::DataErasure as i32,
+            Utc::now().naive_utc(),
+        )
+        .execute(&mut tx)
+        .await?;
+
+        let notifications = sqlx::query!(
+            "DELETE FROM send_notifications
+                WHERE subscription_id IN (
+                    SELECT s.id_subscription
+                    FROM subscription AS s
+                    LEFT JOIN birthday AS b
+                    ON s.birthday_id = b.id_birthday
+                    WHERE s.user_id = $1
+                    OR b.user_id = $1
+                );",
+            user_id
+        )
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+
+        let birthday_ids: Vec<i32> = sqlx::query!(
+            "SELECT id_birthday FROM birthday WHERE user_id = $1;",
+            user_id
+        )
+        .fetch_all(&mut tx)
+        .await?
+        .into_iter()
+        .map(|row| row.id_birthday)
+        .collect();
+
+        let subscribers =
+            Subscription::delete_all_by_birthday_ids(&mut tx, &birthday_ids, user_id as u64)
+                .await?;
+
+        let subscriptions = sqlx::query!("DELETE FROM subscription WHERE user_id = $1;", user_id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+
+        sqlx::query!("DELETE FROM user_settings WHERE user_id = $1;", user_id)
+            .execute(&mut tx)
+            .await?;
+
+        sqlx::query!("DELETE FROM pending_import WHERE user_id = $1;", user_id)
+            .execute(&mut tx)
+            .await?;
+
+        sqlx::query!("DELETE FROM calendar_token WHERE user_id = $1;", user_id)
+            .execute(&mut tx)
+            .await?;
+
+        sqlx::query!(
+            "DELETE FROM role_assignments
+                WHERE user_id = $1
+                AND removed_date IS NOT NULL;",
+            user_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        let birthdays = sqlx::query!("DELETE FROM birthday WHERE user_id = $1;", user_id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+
+        // Last, so it also covers the entries of subscriptions to the user's own birthdays.
+        sqlx::query!(
+            "UPDATE audit_log SET before_data = NULL, after_data = NULL
+                WHERE target_id = $1;",
+            user_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(UserDataErasure {
+            birthdays,
+            subscriptions,
+            subscribers,
+            notifications,
+        })
+    }
+}
+
+/// All data the bot stores about a user, as documented in the readme.
+///
+/// Discord ids are serialized as strings, as they exceed the range of json numbers.
+#[derive(Serialize)]
+pub struct UserDataExport {
+    pub schema_version: u32,
+    pub user_id: String,
+    pub export_date: NaiveDateTime,
+    pub timezone: Option<String>,
+    pub birthdays: Vec<BirthdayExport>,
+    pub subscriptions: Vec<SubscriptionExport>,
+    pub subscribers: Vec<SubscriptionExport>,
+    pub notifications: Vec<NotificationExport>,
+}
+
+#[derive(Serialize)]
+pub struct BirthdayExport {
+    pub id: i32,
+    pub guild_id: String,
+    pub date: NaiveDate,
+    pub year: Option<i32>,
+    pub hide_year: bool,
+    pub visibility: i32,
+    pub create_date: NaiveDateTime,
+    pub modify_date: Option<NaiveDateTime>,
+    pub modified_by: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SubscriptionExport {
+    pub id: i32,
+    pub guild_id: String,
+    pub user_id: String,
+    pub birthday_id: i32,
+    pub notify_time: Option<NaiveTime>,
+    pub reminder_days: Vec<i32>,
+    pub create_date: NaiveDateTime,
+    pub modify_date: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct NotificationExport {
+    pub id: i32,
+    pub subscription_id: i32,
+    pub year: i32,
+    pub days_before: i32,
+    pub create_date: NaiveDateTime,
+}
+
+impl UserDataExport {
+    /// Gathers all data about a user across every guild.
+    pub async fn collect(
+        db: &PgPool,
+        user_id: u64,
+        export_date: NaiveDateTime,
+    ) -> Result<UserDataExport, sqlx::Error> {
+        let settings = UserSettings::get(db, user_id).await?;
+        let birthdays = Birthday::get_all_by_user(db, user_id).await?;
+        let subscriptions = Subscription::get_all_by_user(db, user_id).await?;
+        let subscribers = Subscription::get_all_by_birthday_user(db, user_id).await?;
+
+        let subscription_ids: Vec<i32> = subscriptions
+            .iter()
+            .chain(subscribers.iter())
+            .map(|s| s.id_subscription)
+            .collect();
+        let notifications =
+            SendNotification::get_all_by_subscription_ids(db, &subscription_ids).await?;
+
+        Ok(UserDataExport {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            user_id: user_id.to_string(),
+            export_date,
+            timezone: settings.map(|s| s.timezone().name().to_string()),
+            birthdays: birthdays.iter().map(BirthdayExport::from).collect(),
+            subscriptions: subscriptions.iter().map(SubscriptionExport::from).collect(),
+            subscribers: subscribers.iter().map(SubscriptionExport::from).collect(),
+            notifications: notifications.iter().map(NotificationExport::from).collect(),
+        })
+    }
+}
+
+impl From<&Birthday> for BirthdayExport {
+    fn from(birthday: &Birthday) -> Self {
+        BirthdayExport {
+            id: birthday.id_birthday,
+            guild_id: birthday.guild_id().to_string(),
+            date: birthday.date.date(),
+            year: birthday.year,
+            hide_year: birthday.hide_year,
+            visibility: birthday.visibility() as i32,
+            create_date: birthday.create_date,
+            modify_date: birthday.modify_date,
+            modified_by: birthday.modified_by().map(|user_id| user_id.to_string()),
+        }
+    }
+}
+
+impl From<&Subscription> for SubscriptionExport {
+    fn from(subscription: &Subscription) -> Self {
+        SubscriptionExport {
+            id: subscription.id_subscription,
+            guild_id: subscription.guild_id().to_string(),
+            user_id: subscription.user_id().to_string(),
+            birthday_id: subscription.birthday_id,
+            notify_time: subscription.notify_time,
+            reminder_days: subscription.reminder_days.clone(),
+            create_date: subscription.create_date,
+            modify_date: subscription.modify_date,
+        }
+    }
+}
+
+impl From<&SendNotification> for NotificationExport {
+    fn from(notification: &SendNotification) -> Self {
+        NotificationExport {
+            id: notification.id_send_notification,
+            subscription_id: notification.subscription_id,
+            year: notification.current_year,
+            days_before: notification.days_before,
+            create_date: notification.create_date,
+        }
+    }
+}
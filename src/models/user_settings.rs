@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use chrono_tz::Tz;
+use sqlx::{types::chrono::NaiveDateTime, PgPool, Postgres, Transaction};
+
+/// Settings of a user which apply on every guild, independent of having a birthday.
+#[derive(Clone, Debug)]
+pub struct UserSettings {
+    pub id_user_settings: i32,
+    user_id: i64,
+    timezone: String,
+    pub create_date: NaiveDateTime,
+    pub modify_date: Option<NaiveDateTime>,
+}
+
+impl UserSettings {
+    pub fn new(user_id: u64, create_date: NaiveDateTime) -> UserSettings {
+        UserSettings {
+            id_user_settings: 0,
+            user_id: user_id as i64,
+            timezone: Tz::UTC.name().to_string(),
+            create_date,
+            modify_date: None,
+        }
+    }
+
+    pub async fn get(db: &PgPool, user_id: u64) -> Result<Option<UserSettings>, sqlx::Error> {
+        let settings: Option<UserSettings> = sqlx::query_as!(
+            UserSettings,
+            "SELECT id_user_settings, user_id, timezone, create_date, modify_date
+                FROM user_settings
+                WHERE user_id = $1;",
+            (user_id as i64),
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .nth(0);
+
+        Ok(settings)
+    }
+
+    pub async fn get_or_default(
+        db: &PgPool,
+        user_id: u64,
+        create_date: NaiveDateTime,
+    ) -> Result<UserSettings, sqlx::Error> {
+        Ok(UserSettings::get(db, user_id)
+            .await?
+            .unwrap_or_else(|| UserSettings::new(user_id, create_date)))
+    }
+
+    /// Loads the timezones of several users at once, users without settings are left out.
+    pub async fn get_timezones(
+        db: &PgPool,
+        user_ids: &[u64],
+    ) -> Result<HashMap<u64, Tz>, sqlx::Error> {
+        let user_ids: Vec<i64> = user_ids.iter().map(|id| *id as i64).collect();
+
+        let settings: Vec<UserSettings> = sqlx::query_as!(
+            UserSettings,
+            "SELECT id_user_settings, user_id, timezone, create_date, modify_date
+                FROM user_settings
+                WHERE user_id = ANY($1);",
+            &user_ids
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(settings
+            .iter()
+            .map(|s| (s.user_id(), s.timezone()))
+            .collect())
+    }
+
+    /// Returns the timezone of the user, UTC if none has been set.
+    pub async fn get_timezone(db: &PgPool, user_id: u64) -> Result<Tz, sqlx::Error> {
+        Ok(UserSettings::get(db, user_id)
+            .await?
+            .map(|s| s.timezone())
+            .unwrap_or(Tz::UTC))
+    }
+
+    /// Inserts the settings or updates the existing ones of the user.
+    pub async fn save(&mut self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let id = sqlx::query!(
+            "INSERT INTO user_settings
+                (user_id, timezone, create_date, modify_date)
+                VALUES
+                ($1, $2, $3, $4)
+                ON CONFLICT (user_id)
+                DO UPDATE SET timezone = EXCLUDED.timezone, modify_date = EXCLUDED.modify_date
+                RETURNING id_user_settings;",
+            self.user_id,
+            self.timezone,
+            self.create_date,
+            self.modify_date,
+        )
+        .fetch_one(db)
+        .await?
+        .id_user_settings;
+
+        self.id_user_settings = id;
+
+        Ok(())
+    }
+
+    /// Inserts the settings within a transaction, the existing settings of the user are kept.
+    pub async fn insert_or_keep_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT INTO user_settings
+                (user_id, timezone, create_date)
+                VALUES
+                ($1, $2, $3)
+                ON CONFLICT (user_id)
+                DO NOTHING;",
+            self.user_id,
+            self.timezone,
+            self.create_date,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn user_id(&self) -> u64 {
+        self.user_id as u64
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    pub fn set_timezone(&mut self, timezone: Tz) {
+        self.timezone = timezone.name().to_string();
+    }
+}
@@ -0,0 +1,316 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Decides on which day birthdays on the 29th of February are celebrated in non-leap years.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LeapDayPolicy {
+    #[default]
+    February28 = 0,
+    March1 = 1,
+    LeapYearsOnly = 2,
+}
+
+impl LeapDayPolicy {
+    pub fn from_i32(value: i32) -> Option<LeapDayPolicy> {
+        match value {
+            0 => Some(LeapDayPolicy::February28),
+            1 => Some(LeapDayPolicy::March1),
+            2 => Some(LeapDayPolicy::LeapYearsOnly),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the date on which a birthday is celebrated in the given year.
+///
+/// Returns `None` if the birthday is not celebrated in that year, which is only
+/// the case for the 29th of February with [`LeapDayPolicy::LeapYearsOnly`].
+pub fn occurrence_in_year(birthday: NaiveDate, year: i32, policy: LeapDayPolicy) -> Option<NaiveDate> {
+    if let Some(date) = NaiveDate::from_ymd_opt(year, birthday.month(), birthday.day()) {
+        return Some(date);
+    }
+
+    match policy {
+        LeapDayPolicy::February28 => NaiveDate::from_ymd_opt(year, 2, 28),
+        LeapDayPolicy::March1 => NaiveDate::from_ymd_opt(year, 3, 1),
+        LeapDayPolicy::LeapYearsOnly => None,
+    }
+}
+
+/// Returns the first day on or after `from` on which the birthday is celebrated.
+pub fn next_occurrence(birthday: NaiveDate, from: NaiveDate, policy: LeapDayPolicy) -> NaiveDate {
+    let mut year = from.year();
+
+    loop {
+        if let Some(date) = occurrence_in_year(birthday, year, policy) {
+            if date >= from {
+                return date;
+            }
+        }
+
+        year += 1;
+    }
+}
+
+/// Returns the month and day pairs of all birthdays which might be celebrated on the given day.
+///
+/// Besides the day itself this contains the 29th of February on days which can
+/// stand in for it in non-leap years.
+pub fn candidate_days(day: NaiveDate) -> Vec<(u32, u32)> {
+    let mut days = vec![(day.month(), day.day())];
+
+    let is_leap_year = NaiveDate::from_ymd_opt(day.year(), 2, 29).is_some();
+    let is_substitute = (day.month(), day.day()) == (2, 28) || (day.month(), day.day()) == (3, 1);
+
+    if !is_leap_year && is_substitute {
+        days.push((2, 29));
+    }
+
+    days
+}
+
+/// Checks if a notification about a birthday celebrated on `date` in `celebrant_tz` is due.
+///
+/// The notification is due once `time` has passed in `subscriber_tz`, but never before
+/// the celebrant's day has started and at the latest one hour before it ends. Without a
+/// preferred time the notification is due as soon as the celebrant's day has started.
+pub fn is_delivery_due(
+    now: DateTime<Utc>,
+    date: NaiveDate,
+    celebrant_tz: Tz,
+    subscriber_tz: Tz,
+    time: Option<NaiveTime>,
+) -> bool {
+    let day_start = day_start(date, celebrant_tz);
+    let latest = day_start + Duration::hours(23);
+
+    let due = match time {
+        Some(time) => to_utc(subscriber_tz, date.and_time(time)).clamp(day_start, latest),
+        None => day_start,
+    };
+
+    now >= due
+}
+
+/// Returns the moment at which the given day starts in the given timezone.
+pub fn day_start(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    to_utc(tz, date.and_time(NaiveTime::MIN))
+}
+
+/// Converts a local date and time to utc, moving times skipped by daylight saving time forward.
+fn to_utc(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local).earliest() {
+        Some(date_time) => date_time.with_timezone(&Utc),
+        None => to_utc(tz, local + Duration::minutes(30)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::{America, Asia, Pacific, UTC};
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn occurrence_in_year_keeps_month_and_day() {
+        let policy = LeapDayPolicy::default();
+
+        assert_eq!(
+            occurrence_in_year(date(1990, 12, 31), 2023, policy),
+            Some(date(2023, 12, 31))
+        );
+        assert_eq!(
+            occurrence_in_year(date(1990, 1, 1), 2024, policy),
+            Some(date(2024, 1, 1))
+        );
+    }
+
+    #[test]
+    fn next_occurrence_crosses_the_year_boundary() {
+        let policy = LeapDayPolicy::default();
+
+        assert_eq!(
+            next_occurrence(date(1990, 12, 31), date(2023, 12, 31), policy),
+            date(2023, 12, 31)
+        );
+        assert_eq!(
+            next_occurrence(date(1990, 1, 1), date(2023, 12, 31), policy),
+            date(2024, 1, 1)
+        );
+        assert_eq!(
+            next_occurrence(date(1990, 12, 31), date(2024, 1, 1), policy),
+            date(2024, 12, 31)
+        );
+    }
+
+    #[test]
+    fn candidate_days_around_the_year_boundary() {
+        assert_eq!(candidate_days(date(2023, 12, 31)), vec![(12, 31)]);
+        assert_eq!(candidate_days(date(2024, 1, 1)), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn candidate_days_include_the_leap_day_in_non_leap_years() {
+        assert_eq!(candidate_days(date(2023, 2, 28)), vec![(2, 28), (2, 29)]);
+        assert_eq!(candidate_days(date(2023, 3, 1)), vec![(3, 1), (2, 29)]);
+        assert_eq!(candidate_days(date(2024, 2, 28)), vec![(2, 28)]);
+        assert_eq!(candidate_days(date(2024, 3, 1)), vec![(3, 1)]);
+    }
+
+    #[test]
+    fn delivery_is_due_when_the_celebrants_day_starts() {
+        // New year starts in Auckland (UTC+13) while it is still the 31st of December in utc.
+        let date = date(2024, 1, 1);
+
+        assert!(!is_delivery_due(
+            utc(2023, 12, 31, 10, 59),
+            date,
+            Pacific::Auckland,
+            UTC,
+            None
+        ));
+        assert!(is_delivery_due(
+            utc(2023, 12, 31, 11, 0),
+            date,
+            Pacific::Auckland,
+            UTC,
+            None
+        ));
+        assert!(!is_delivery_due(
+            utc(2024, 1, 1, 4, 59),
+            date,
+            America::New_York,
+            UTC,
+            None
+        ));
+        assert!(is_delivery_due(
+            utc(2024, 1, 1, 5, 0),
+            date,
+            America::New_York,
+            UTC,
+            None
+        ));
+    }
+
+    #[test]
+    fn delivery_time_is_clamped_to_the_celebrants_day() {
+        let date = date(2024, 1, 1);
+        let time = NaiveTime::from_hms_opt(8, 0, 0);
+
+        // 08:00 in Tokyo is 23:00 utc on the day before, so the start of the day in utc wins.
+        assert!(!is_delivery_due(
+            utc(2023, 12, 31, 23, 30),
+            date,
+            UTC,
+            Asia::Tokyo,
+            time
+        ));
+        assert!(is_delivery_due(
+            utc(2024, 1, 1, 0, 0),
+            date,
+            UTC,
+            Asia::Tokyo,
+            time
+        ));
+
+        // 08:00 in Los Angeles is after the day in Auckland ended, so one hour before its end wins.
+        assert!(!is_delivery_due(
+            utc(2024, 1, 1, 9, 59),
+            date,
+            Pacific::Auckland,
+            America::Los_Angeles,
+            time
+        ));
+        assert!(is_delivery_due(
+            utc(2024, 1, 1, 10, 0),
+            date,
+            Pacific::Auckland,
+            America::Los_Angeles,
+            time
+        ));
+    }
+
+    #[test]
+    fn delivery_time_is_in_the_subscribers_timezone() {
+        let date = date(2024, 1, 1);
+        let time = NaiveTime::from_hms_opt(9, 0, 0);
+
+        assert!(!is_delivery_due(
+            utc(2024, 1, 1, 13, 59),
+            date,
+            UTC,
+            America::New_York,
+            time
+        ));
+        assert!(is_delivery_due(
+            utc(2024, 1, 1, 14, 0),
+            date,
+            UTC,
+            America::New_York,
+            time
+        ));
+    }
+
+    #[test]
+    fn leap_day_policies_over_four_years() {
+        let birthday = date(2000, 2, 29);
+        let years = 2023..=2026;
+
+        let dates = |policy| -> Vec<Option<NaiveDate>> {
+            years
+                .clone()
+                .map(|year| occurrence_in_year(birthday, year, policy))
+                .collect()
+        };
+
+        assert_eq!(
+            dates(LeapDayPolicy::February28),
+            vec![
+                Some(date(2023, 2, 28)),
+                Some(date(2024, 2, 29)),
+                Some(date(2025, 2, 28)),
+                Some(date(2026, 2, 28)),
+            ]
+        );
+        assert_eq!(
+            dates(LeapDayPolicy::March1),
+            vec![
+                Some(date(2023, 3, 1)),
+                Some(date(2024, 2, 29)),
+                Some(date(2025, 3, 1)),
+                Some(date(2026, 3, 1)),
+            ]
+        );
+        assert_eq!(
+            dates(LeapDayPolicy::LeapYearsOnly),
+            vec![None, Some(date(2024, 2, 29)), None, None]
+        );
+    }
+
+    #[test]
+    fn next_occurrence_skips_non_leap_years() {
+        let birthday = date(2000, 2, 29);
+
+        assert_eq!(
+            next_occurrence(birthday, date(2025, 1, 1), LeapDayPolicy::LeapYearsOnly),
+            date(2028, 2, 29)
+        );
+        assert_eq!(
+            next_occurrence(birthday, date(2025, 3, 1), LeapDayPolicy::February28),
+            date(2026, 2, 28)
+        );
+        assert_eq!(
+            next_occurrence(birthday, date(2025, 3, 1), LeapDayPolicy::March1),
+            date(2025, 3, 1)
+        );
+    }
+}
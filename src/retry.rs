@@ -0,0 +1,39 @@
+use chrono::{Duration, NaiveDateTime};
+
+/// The number of attempts after which a failing announcement or role assignment is given up.
+pub const MAX_ATTEMPTS: i32 = 5;
+/// The delay before the first retry, doubled after every further failure.
+const FIRST_RETRY_MINUTES: i64 = 5;
+
+/// Returns when to try again after the given number of failed attempts, `None` once it is given up.
+pub fn next_attempt_date(attempts: i32, failure_date: NaiveDateTime) -> Option<NaiveDateTime> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+
+    let delay = FIRST_RETRY_MINUTES << (attempts - 1).max(0);
+
+    Some(failure_date + Duration::minutes(delay))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn delay_doubles_until_given_up() {
+        let failure_date = NaiveDate::from_ymd_opt(2023, 12, 31)
+            .and_then(|date| date.and_hms_opt(23, 50, 0))
+            .unwrap();
+        let delays: Vec<Option<i64>> = (1..=MAX_ATTEMPTS)
+            .map(|attempts| {
+                next_attempt_date(attempts, failure_date)
+                    .map(|date| (date - failure_date).num_minutes())
+            })
+            .collect();
+
+        assert_eq!(delays, vec![Some(5), Some(10), Some(20), Some(40), None]);
+    }
+}
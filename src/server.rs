@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serenity::http::Http;
+use sqlx::{types::chrono::Utc, PgPool};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error};
+
+use crate::ical::{self, BirthdayEvent};
+use crate::models::birthday::Birthday;
+use crate::models::calendar_token::CalendarToken;
+use crate::models::guild_settings::GuildSettings;
+use crate::models::subscription::Subscription;
+
+/// The path of the feeds, followed by the token and `.ics`.
+pub const CALENDAR_PATH: &str = "/calendar/";
+/// Only the request line is needed, longer requests are cut off.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long resolved names are reused, calendar apps poll feeds every few hours.
+const NAME_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+const STATUS_OK: &str = "200 OK";
+const STATUS_BAD_REQUEST: &str = "400 Bad Request";
+const STATUS_NOT_FOUND: &str = "404 Not Found";
+const STATUS_METHOD_NOT_ALLOWED: &str = "405 Method Not Allowed";
+const STATUS_ERROR: &str = "500 Internal Server Error";
+
+/// What all connections share, the names are cached across connections.
+struct FeedContext {
+    db: PgPool,
+    http: Arc<Http>,
+    names: NameCache,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+enum NameKey {
+    Guild(u64),
+    Member(u64, u64),
+}
+
+/// Names resolved from Discord, so polling a feed does not request every name again.
+#[derive(Default)]
+struct NameCache {
+    entries: Mutex<HashMap<NameKey, (String, Instant)>>,
+}
+
+impl NameCache {
+    fn get(&self, key: NameKey) -> Option<String> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        entries
+            .get(&key)
+            .filter(|(_, resolved)| resolved.elapsed() < NAME_CACHE_TTL)
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Stores a name and drops all expired ones, so names of users who left are not kept.
+    fn insert(&self, key: NameKey, name: String) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        entries.retain(|_, (_, resolved)| resolved.elapsed() < NAME_CACHE_TTL);
+        entries.insert(key, (name, Instant::now()));
+    }
+}
+
+/// Serves the calendar feeds of all users on the listener.
+pub async fn serve(listener: TcpListener, db: PgPool, http: Arc<Http>) {
+    let context = FeedContext {
+        db,
+        http,
+        names: NameCache::default(),
+    };
+
+    accept_connections(listener, Arc::new(context)).await
+}
+
+async fn accept_connections(listener: TcpListener, context: Arc<FeedContext>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(why) => {
+                error!("Cannot accept calendar connection: {}", why);
+                continue;
+            }
+        };
+
+        let context = Arc::clone(&context);
+
+        tokio::spawn(async move {
+            if let Err(why) = handle_connection(stream, &context).await {
+                debug!("Cannot answer calendar request: {}", why);
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, context: &FeedContext) -> std::io::Result<()> {
+    let head = match tokio::time::timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await {
+        Ok(head) => head?,
+        Err(_) => return Ok(()),
+    };
+
+    let response = match parse_token(&head) {
+        Ok(token) => match gen_feed(context, token).await {
+            Ok(Some(calendar)) => {
+                gen_response(STATUS_OK, "text/calendar; charset=utf-8", &calendar)
+            }
+            Ok(None) => gen_response(STATUS_NOT_FOUND, "text/plain", STATUS_NOT_FOUND),
+            Err(why) => {
+                error!("Cannot generate calendar feed: {}", why);
+                gen_response(STATUS_ERROR, "text/plain", STATUS_ERROR)
+            }
+        },
+        Err(status) => gen_response(status, "text/plain", status),
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Reads until the end of the request head or [`MAX_REQUEST_SIZE`].
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 1024];
+
+    while buffer.len() < MAX_REQUEST_SIZE {
+        let read = stream.read(&mut chunk).await?;
+
+        if read == 0 {
+            break;
+        }
+
+        buffer.extend_from_slice(&chunk[..read]);
+
+        if buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+            break;
+        }
+    }
+
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// Returns the token of a `GET /calendar/<token>.ics` request, or the status to answer with.
+fn parse_token(head: &str) -> Result<&str, &'static str> {
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(target)) => target
+            .split('?')
+            .next()
+            .and_then(|path| path.strip_prefix(CALENDAR_PATH))
+            .and_then(|file| file.strip_suffix(".ics"))
+            .filter(|token| !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric()))
+            .ok_or(STATUS_NOT_FOUND),
+        (Some(_), Some(_)) => Err(STATUS_METHOD_NOT_ALLOWED),
+        _ => Err(STATUS_BAD_REQUEST),
+    }
+}
+
+/// Generates the calendar of all birthdays the owner of the token is subscribed to on its guild,
+/// `None` if the token is unknown.
+async fn gen_feed(context: &FeedContext, token: &str) -> Result<Option<String>, sqlx::Error> {
+    let db = &context.db;
+    let calendar_token = match CalendarToken::get_by_token(db, token).await? {
+        Some(calendar_token) => calendar_token,
+        None => return Ok(None),
+    };
+
+    let guild_id = calendar_token.guild_id();
+    let subscriptions =
+        Subscription::get_all_by_guild_and_user(db, guild_id, calendar_token.user_id()).await?;
+
+    let mut events = Vec::new();
+
+    for subscription in subscriptions {
+        let birthday = match Birthday::get_by_id(db, subscription.birthday_id).await? {
+            Some(b) if b.is_visible_to(calendar_token.user_id(), true) => b,
+            _ => continue,
+        };
+
+        let name = get_member_name(context, guild_id, birthday.user_id()).await;
+        events.push(BirthdayEvent::from_birthday(&birthday, &name));
+    }
+
+    let settings = GuildSettings::get_or_default(db, guild_id, Utc::now().naive_utc()).await?;
+    let name = match get_guild_name(context, guild_id).await {
+        Some(guild_name) => format!("Birthdays on {}", guild_name),
+        None => String::from("Birthdays"),
+    };
+
+    Ok(Some(ical::gen_calendar(
+        &name,
+        &events,
+        settings.leap_day_policy(),
+        Utc::now().naive_utc(),
+    )))
+}
+
+/// Returns the display name of a member, or the user id if the member can not be found.
+async fn get_member_name(context: &FeedContext, guild_id: u64, user_id: u64) -> String {
+    let key = NameKey::Member(guild_id, user_id);
+
+    if let Some(name) = context.names.get(key) {
+        return name;
+    }
+
+    match context.http.get_member(guild_id, user_id).await {
+        Ok(member) => {
+            let name = member.display_name().to_string();
+            context.names.insert(key, name.clone());
+            name
+        }
+        Err(_) => format!("User {}", user_id),
+    }
+}
+
+async fn get_guild_name(context: &FeedContext, guild_id: u64) -> Option<String> {
+    let key = NameKey::Guild(guild_id);
+
+    if let Some(name) = context.names.get(key) {
+        return Some(name);
+    }
+
+    let name = context.http.get_guild(guild_id).await.ok()?.name;
+    context.names.insert(key, name.clone());
+
+    Some(name)
+}
+
+fn gen_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use sqlx::types::chrono::NaiveDate;
+
+    use super::*;
+
+    const GUILD_ID: u64 = 1;
+    const OWNER_ID: u64 = 10;
+    const BIRTHDAY_USER_ID: u64 = 20;
+    const TOKEN: &str = "abcdef0123456789";
+
+    #[test]
+    fn parses_the_token_of_feed_requests() {
+        assert_eq!(
+            parse_token("GET /calendar/abc123.ics HTTP/1.1\r\n"),
+            Ok("abc123")
+        );
+        assert_eq!(
+            parse_token("GET /calendar/abc123.ics?x=1 HTTP/1.1\r\n"),
+            Ok("abc123")
+        );
+        assert_eq!(
+            parse_token("GET /calendar/abc123 HTTP/1.1\r\n"),
+            Err(STATUS_NOT_FOUND)
+        );
+        assert_eq!(
+            parse_token("GET /calendar/../x.ics HTTP/1.1\r\n"),
+            Err(STATUS_NOT_FOUND)
+        );
+        assert_eq!(
+            parse_token("GET /other/abc.ics HTTP/1.1\r\n"),
+            Err(STATUS_NOT_FOUND)
+        );
+        assert_eq!(
+            parse_token("POST /calendar/abc123.ics HTTP/1.1\r\n"),
+            Err(STATUS_METHOD_NOT_ALLOWED)
+        );
+        assert_eq!(parse_token("garbage"), Err(STATUS_BAD_REQUEST));
+    }
+
+    #[test]
+    fn counts_the_content_length_in_bytes() {
+        let response = gen_response(STATUS_OK, "text/plain", "Tschüss");
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Length: 8\r\n"));
+        assert!(response.ends_with("\r\n\r\nTschüss"));
+    }
+
+    async fn request(address: SocketAddr, head: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(head.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        response
+    }
+
+    #[sqlx::test]
+    async fn serves_feeds_over_http(db: PgPool) {
+        let now = Utc::now().naive_utc();
+        let date = NaiveDate::from_ymd_opt(2000, 5, 17)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .unwrap();
+
+        let mut birthday = Birthday::new(GUILD_ID, BIRTHDAY_USER_ID, date, None, now);
+        birthday.insert(&db, BIRTHDAY_USER_ID).await.unwrap();
+        Subscription::new(GUILD_ID, OWNER_ID, birthday.id_birthday, None, vec![0], now)
+            .insert(&db, OWNER_ID)
+            .await
+            .unwrap();
+        CalendarToken::new(GUILD_ID, OWNER_ID, TOKEN, now)
+            .save(&db)
+            .await
+            .unwrap();
+
+        // The names are cached up front, so the test does not need the Discord api.
+        let names = NameCache::default();
+        names.insert(NameKey::Guild(GUILD_ID), String::from("Test"));
+        names.insert(
+            NameKey::Member(GUILD_ID, BIRTHDAY_USER_ID),
+            String::from("Alice"),
+        );
+        let context = FeedContext {
+            db,
+            http: Arc::new(Http::new("")),
+            names,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(accept_connections(listener, Arc::new(context)));
+
+        let response = request(
+            address,
+            &format!("GET /calendar/{}.ics HTTP/1.1\r\n\r\n", TOKEN),
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/calendar; charset=utf-8\r\n"));
+        assert!(response.contains("X-WR-CALNAME:Birthdays on Test\r\n"));
+        assert!(response.contains("SUMMARY:Birthday of Alice\r\n"));
+
+        let response = request(address, "GET /calendar/unknown.ics HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let response = request(
+            address,
+            &format!("POST /calendar/{}.ics HTTP/1.1\r\n\r\n", TOKEN),
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+
+        let response = request(address, "\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+}
@@ -0,0 +1,112 @@
+use chrono::NaiveDate;
+
+use crate::i18n::Language;
+
+/// The placeholders which can be used in message templates.
+pub const PLACEHOLDERS: [&str; 6] = ["mention", "name", "age", "date", "when", "guild"];
+pub const MAX_TEMPLATE_LENGTH: usize = 1000;
+pub const DEFAULT_ANNOUNCEMENT_TEMPLATE: &str = "Happy birthday {mention}!";
+
+/// The values inserted for the placeholders of a template.
+pub struct TemplateValues {
+    pub mention: String,
+    pub name: String,
+    pub age: Option<i32>,
+    pub date: NaiveDate,
+    pub when: String,
+    pub guild: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    TooLong,
+    UnclosedPlaceholder,
+    UnknownPlaceholder(String),
+}
+
+impl TemplateError {
+    pub fn description(&self, language: Language) -> String {
+        match self {
+            TemplateError::TooLong => language.tr_args(
+                "template.too-long",
+                &[("max", MAX_TEMPLATE_LENGTH.to_string())],
+            ),
+            TemplateError::UnclosedPlaceholder => language.tr("template.unclosed-placeholder"),
+            // The list is inserted first, so a placeholder named like an argument stays as it is.
+            TemplateError::UnknownPlaceholder(name) => language.tr_args(
+                "template.unknown-placeholder",
+                &[
+                    ("placeholders", gen_placeholder_list()),
+                    ("placeholder", format!("{{{}}}", name)),
+                ],
+            ),
+        }
+    }
+}
+
+/// Checks that the template only uses known placeholders.
+pub fn validate(template: &str) -> Result<(), TemplateError> {
+    if template.chars().count() > MAX_TEMPLATE_LENGTH {
+        return Err(TemplateError::TooLong);
+    }
+
+    for name in placeholders(template)? {
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(TemplateError::UnknownPlaceholder(name.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the placeholders of a validated template.
+///
+/// Returns `None` if the template uses `{age}` but the age is unknown, in which
+/// case the caller falls back to the default text.
+pub fn render(template: &str, values: &TemplateValues) -> Option<String> {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}')?;
+        text.push_str(&rest[..start]);
+
+        match &rest[start + 1..end] {
+            "mention" => text.push_str(&values.mention),
+            "name" => text.push_str(&values.name),
+            "age" => text.push_str(&values.age?.to_string()),
+            "date" => text.push_str(&values.date.to_string()),
+            "when" => text.push_str(&values.when),
+            "guild" => text.push_str(&values.guild),
+            _ => text.push_str(&rest[start..=end]),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    text.push_str(rest);
+
+    Some(text)
+}
+
+pub fn gen_placeholder_list() -> String {
+    let names: Vec<String> = PLACEHOLDERS.iter().map(|p| format!("`{{{}}}`", p)).collect();
+    names.join(", ")
+}
+
+fn placeholders(template: &str) -> Result<Vec<&str>, TemplateError> {
+    let mut names = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start + 1..].find(['{', '}']) {
+            Some(offset) if rest[start + 1 + offset..].starts_with('}') => start + 1 + offset,
+            _ => return Err(TemplateError::UnclosedPlaceholder),
+        };
+
+        names.push(&rest[start + 1..end]);
+        rest = &rest[end + 1..];
+    }
+
+    Ok(names)
+}
@@ -1,4 +1,8 @@
-use serenity::model::user::User;
+use serenity::http::Http;
+use serenity::model::{prelude::GuildId, user::User};
+use serenity::prelude::Context;
+
+use crate::i18n::Language;
 
 pub fn get_icon_url(user: &User) -> String {
     if let Some(url) = user.avatar_url() {
@@ -7,3 +11,30 @@ pub fn get_icon_url(user: &User) -> String {
 
     user.default_avatar_url()
 }
+
+/// Returns the display name of a member, or the user id if the member can not be found.
+pub async fn get_member_name(http: &Http, guild_id: GuildId, user_id: u64) -> String {
+    match http.get_member(guild_id.0, user_id).await {
+        Ok(m) => m.display_name().to_string(),
+        Err(_) => format!("User {}", user_id),
+    }
+}
+
+pub async fn get_guild_name(ctx: &Context, guild_id: GuildId) -> String {
+    if let Some(name) = guild_id.name(&ctx.cache) {
+        return name;
+    }
+
+    match guild_id.to_partial_guild(&ctx.http).await {
+        Ok(guild) => guild.name,
+        Err(_) => String::from("this server"),
+    }
+}
+
+/// Returns the language of the guild for messages outside of interactions, like notifications.
+pub fn get_guild_language(ctx: &Context, guild_id: GuildId) -> Language {
+    ctx.cache
+        .guild_field(guild_id, |guild| guild.preferred_locale.clone())
+        .and_then(|locale| Language::from_locale(&locale))
+        .unwrap_or_default()
+}